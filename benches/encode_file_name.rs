@@ -0,0 +1,150 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+#![doc(
+    html_logo_url = "https://raw.githubusercontent.com/maidsafe/QA/master/Images/maidsafe_logo.png",
+    html_favicon_url = "https://maidsafe.net/img/favicon.ico",
+    test(attr(forbid(warnings)))
+)]
+// For explanation of lint checks, run `rustc -W help` or see
+// https://github.com/maidsafe/QA/blob/master/Documentation/Rust%20Lint%20Checks.md
+#![forbid(
+    exceeding_bitshifts,
+    mutable_transmutes,
+    no_mangle_const_items,
+    unknown_crate_types,
+    warnings
+)]
+#![deny(
+    bad_style,
+    deprecated,
+    improper_ctypes,
+    missing_docs,
+    non_shorthand_field_patterns,
+    overflowing_literals,
+    plugin_as_library,
+    stable_features,
+    unconditional_recursion,
+    unknown_lints,
+    unsafe_code,
+    unused,
+    unused_allocation,
+    unused_attributes,
+    unused_comparisons,
+    unused_features,
+    unused_parens,
+    while_true
+)]
+#![warn(
+    trivial_casts,
+    trivial_numeric_casts,
+    unused_extern_crates,
+    unused_import_braces,
+    unused_qualifications,
+    unused_results
+)]
+#![allow(
+    box_pointers,
+    missing_copy_implementations,
+    missing_debug_implementations,
+    variant_size_differences
+)]
+
+//! Compares the cost of trimming the source file path at encode time via the `{fn}` token against
+//! the previous approach of post-processing `#FS#{f}#FE#` markers with a regex.
+//!
+//! Run with `cargo bench --bench encode_file_name`.
+
+#[macro_use]
+extern crate log as logger;
+#[macro_use]
+extern crate unwrap;
+
+use log4rs::encode::pattern::PatternEncoder as Log4rsPatternEncoder;
+use log4rs::encode::writer::simple::SimpleWriter;
+use log4rs::encode::Encode;
+use logger::{Log, LogLevelFilter, LogMetadata, LogRecord};
+use maidsafe_utilities::log::PatternEncoder;
+use regex::Regex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+
+const ITERATIONS: u32 = 200_000;
+
+static USE_REGEX: AtomicBool = AtomicBool::new(false);
+
+struct BenchLogger {
+    legacy: Log4rsPatternEncoder,
+    regex: Regex,
+    current: PatternEncoder,
+}
+
+impl Log for BenchLogger {
+    fn enabled(&self, _metadata: &LogMetadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &LogRecord) {
+        let msg = if USE_REGEX.load(Ordering::Relaxed) {
+            self.encode_with_regex(record)
+        } else {
+            let mut msg = Vec::new();
+            unwrap!(self.current.encode(&mut SimpleWriter(&mut msg), record));
+            msg
+        };
+        assert!(!msg.is_empty());
+    }
+}
+
+impl BenchLogger {
+    // This mirrors what the `AsyncLog` worker thread used to do with every record.
+    fn encode_with_regex(&self, record: &LogRecord) -> Vec<u8> {
+        let mut msg = Vec::new();
+        unwrap!(self.legacy.encode(&mut SimpleWriter(&mut msg), record));
+        let mut str_msg = unwrap!(String::from_utf8(msg));
+        let str_msg_cloned = str_msg.clone();
+        if let Some(file_name_capture) = self.regex.captures(&str_msg_cloned) {
+            if let Some(file_name) = file_name_capture.get(1) {
+                str_msg = self.regex.replace(&str_msg[..], file_name.as_str()).into();
+            }
+        }
+        str_msg.into_bytes()
+    }
+}
+
+fn run(use_regex: bool) -> f64 {
+    USE_REGEX.store(use_regex, Ordering::Relaxed);
+    let start = Instant::now();
+    for i in 0..ITERATIONS {
+        info!("Benchmarking record number {}", i);
+    }
+    let elapsed = start.elapsed();
+    (elapsed.as_secs() as f64 * 1e9 + f64::from(elapsed.subsec_nanos())) / f64::from(ITERATIONS)
+}
+
+fn main() {
+    unwrap!(logger::set_logger(|max_level| {
+        max_level.set(LogLevelFilter::Trace);
+        Box::new(BenchLogger {
+            legacy: Log4rsPatternEncoder::new("{l} {d(%H:%M:%S.%f)} [{M} #FS#{f}#FE#:{L}] {m}{n}"),
+            regex: unwrap!(Regex::new(r"#FS#?.*[/\\#]([^#]+)#FE#")),
+            current: PatternEncoder::new("{l} {d(%H:%M:%S.%f)} [{M} {fn}:{L}] {m}{n}"),
+        })
+    }));
+
+    // Warm up both paths before measuring.
+    let _ = run(true);
+    let _ = run(false);
+
+    let regex_ns = run(true);
+    let token_ns = run(false);
+    println!("#FS#{{f}}#FE# with regex: {:>8.0} ns/record", regex_ns);
+    println!("{{fn}} token:             {:>8.0} ns/record", token_ns);
+    println!("speed-up:                {:>8.2}x", regex_ns / token_ns);
+}
@@ -0,0 +1,103 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+//! Converts binary logs written with `encoding = "binary"` back into the default text format.
+//!
+//! Usage: `maidsafe_log_decode [--show-thread-name] [--timestamp time|iso8601]
+//! [--timestamp-zone local|utc] [--timestamp-precision s|ms|us|ns] [FILE]...`
+//!
+//! The timestamp options match the `timestamp*` keys of `log.toml` and default to the local time
+//! of day in nanoseconds.  Reads standard input if no files are given.
+
+use maidsafe_utilities::log::{BinaryRecords, TimestampFormat};
+use std::env;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::process;
+
+const USAGE: &str = "Usage: maidsafe_log_decode [--show-thread-name] [--timestamp time|iso8601] \
+                     [--timestamp-zone local|utc] [--timestamp-precision s|ms|us|ns] [FILE]...";
+
+fn decode<R: Read, W: Write>(
+    input: R,
+    output: &mut W,
+    timestamp: TimestampFormat,
+    show_thread_name: bool,
+) -> Result<(), String> {
+    for record in BinaryRecords::new(input) {
+        let record = record.map_err(|e| format!("{}", e))?;
+        output
+            .write_all(record.to_text(timestamp, show_thread_name).as_bytes())
+            .map_err(|e| format!("{}", e))?;
+    }
+    Ok(())
+}
+
+fn run() -> Result<(), String> {
+    let mut show_thread_name = false;
+    let mut timestamp = TimestampFormat::new();
+    let mut files = Vec::new();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .ok_or_else(|| format!("missing value for {}", name))
+        };
+        match &arg[..] {
+            "--show-thread-name" => show_thread_name = true,
+            "--timestamp" => {
+                timestamp = match &value(&arg)?.to_lowercase()[..] {
+                    "time" => timestamp.date(false),
+                    "iso8601" => timestamp.date(true),
+                    other => return Err(format!("invalid timestamp: {}", other)),
+                }
+            }
+            "--timestamp-zone" => {
+                timestamp = match &value(&arg)?.to_lowercase()[..] {
+                    "local" => timestamp.utc(false),
+                    "utc" => timestamp.utc(true),
+                    other => return Err(format!("invalid timestamp zone: {}", other)),
+                }
+            }
+            "--timestamp-precision" => {
+                let precision = value(&arg)?;
+                timestamp = timestamp.precision(
+                    precision
+                        .parse()
+                        .map_err(|()| format!("invalid timestamp precision: {}", precision))?,
+                );
+            }
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return Ok(());
+            }
+            _ => files.push(arg),
+        }
+    }
+
+    let stdout = io::stdout();
+    let mut output = stdout.lock();
+    if files.is_empty() {
+        let stdin = io::stdin();
+        return decode(stdin.lock(), &mut output, timestamp, show_thread_name);
+    }
+    for file in files {
+        let input = File::open(&file).map_err(|e| format!("{}: {}", file, e))?;
+        decode(input, &mut output, timestamp, show_thread_name)
+            .map_err(|e| format!("{}: {}", file, e))?;
+    }
+    Ok(())
+}
+
+fn main() {
+    if let Err(error) = run() {
+        eprintln!("maidsafe_log_decode: {}", error);
+        process::exit(1);
+    }
+}
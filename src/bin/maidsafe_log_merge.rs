@@ -0,0 +1,48 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+//! Merges several text or JSON lines logs into one chronologically ordered stream, prefixing each
+//! entry with the name of the file it was read from.
+//!
+//! Usage: `maidsafe_log_merge FILE...`
+
+use maidsafe_utilities::log::merge::Merge;
+use std::env;
+use std::fs::File;
+use std::io::{self, BufReader, Write};
+use std::process;
+
+fn run() -> Result<(), String> {
+    let files: Vec<String> = env::args().skip(1).collect();
+    if files.is_empty() || files.iter().any(|arg| arg == "-h" || arg == "--help") {
+        println!("Usage: maidsafe_log_merge FILE...");
+        return Ok(());
+    }
+
+    let mut merge = Merge::new();
+    for file in files {
+        let input = File::open(&file).map_err(|e| format!("{}: {}", file, e))?;
+        merge.add(file, BufReader::new(input));
+    }
+
+    let stdout = io::stdout();
+    let mut output = stdout.lock();
+    for entry in merge {
+        let entry = entry.map_err(|e| format!("{}", e))?;
+        write!(output, "{}", entry).map_err(|e| format!("{}", e))?;
+    }
+    Ok(())
+}
+
+fn main() {
+    if let Err(error) = run() {
+        eprintln!("maidsafe_log_merge: {}", error);
+        process::exit(1);
+    }
+}
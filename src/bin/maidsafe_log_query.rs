@@ -0,0 +1,85 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+//! Prints the entries of text or JSON lines logs which match the given criteria, in the default
+//! text format.
+//!
+//! Usage: `maidsafe_log_query [--level LEVEL] [--module MODULE] [--thread THREAD]
+//! [--since TIME] [--until TIME] [FILE]...`
+//!
+//! Times are either `HH:MM:SS[.fraction]` or RFC 3339.  Reads standard input if no files are
+//! given.
+
+use log::LogLevelFilter;
+use maidsafe_utilities::log::reader::{Filter, LogReader};
+use std::env;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::process;
+use std::str::FromStr;
+
+const USAGE: &str = "Usage: maidsafe_log_query [--level LEVEL] [--module MODULE] \
+                     [--thread THREAD] [--since TIME] [--until TIME] [FILE]...";
+
+fn query<R: BufRead, W: Write>(input: R, output: &mut W, filter: &Filter) -> Result<(), String> {
+    for entry in LogReader::new(input).filter(filter.clone()) {
+        let entry = entry.map_err(|e| format!("{}", e))?;
+        write!(output, "{}", entry).map_err(|e| format!("{}", e))?;
+    }
+    Ok(())
+}
+
+fn run() -> Result<(), String> {
+    let mut filter = Filter::new();
+    let mut files = Vec::new();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .ok_or_else(|| format!("missing value for {}", name))
+        };
+        match &arg[..] {
+            "--level" => {
+                let level = value(&arg)?;
+                let level = LogLevelFilter::from_str(&level)
+                    .map_err(|_| format!("invalid level: {}", level))?;
+                filter = filter.level(level);
+            }
+            "--module" => filter = filter.module(value(&arg)?),
+            "--thread" => filter = filter.thread(value(&arg)?),
+            "--since" => filter = filter.since(value(&arg)?.parse()?),
+            "--until" => filter = filter.until(value(&arg)?.parse()?),
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return Ok(());
+            }
+            _ => files.push(arg),
+        }
+    }
+
+    let stdout = io::stdout();
+    let mut output = stdout.lock();
+    if files.is_empty() {
+        let stdin = io::stdin();
+        return query(stdin.lock(), &mut output, &filter);
+    }
+    for file in files {
+        let input = File::open(&file).map_err(|e| format!("{}: {}", file, e))?;
+        query(BufReader::new(input), &mut output, &filter)
+            .map_err(|e| format!("{}: {}", file, e))?;
+    }
+    Ok(())
+}
+
+fn main() {
+    if let Err(error) = run() {
+        eprintln!("maidsafe_log_query: {}", error);
+        process::exit(1);
+    }
+}
@@ -78,6 +78,8 @@ use log4rs;
 extern crate quick_error;
 use rand;
 #[macro_use]
+extern crate serde_derive;
+#[macro_use]
 extern crate unwrap;
 use ws;
 
@@ -92,4 +94,10 @@ pub mod serialisation;
 /// Utilities related to threading.
 pub mod thread;
 
+#[doc(hidden)]
+pub use crate::log_or_panic::__record_log_or_panic;
+pub use crate::log_or_panic::{
+    clear_log_or_panic_callback, is_log_or_panic_forced, log_or_panic_sites,
+    set_log_or_panic_callback, set_log_or_panic_forced, LogOrPanicSite,
+};
 pub use crate::seeded_rng::SeededRng;
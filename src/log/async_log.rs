@@ -9,199 +9,283 @@
 
 // TODO: consider contributing this code to the log4rs crate.
 
+use crate::log::binary::{BinaryEncoder, BinaryRecord};
+use crate::log::colour::{ColourChoice, ColourEncoder};
+use crate::log::facade;
+use crate::log::file_name;
+use crate::log::json::{JsonEncoder, JsonRecord};
+use crate::log::pattern;
+use crate::log::rate_limit::{CallSite, RateLimit, RateLimiter, Summary};
+use crate::log::redaction;
+use crate::log::sampling::{SampleRate, Sampling};
+use crate::log::stats::Counters;
+use crate::log::timestamp::TimestampFormat;
 use crate::log::web_socket::WebSocket;
 use crate::thread::{self, Joiner};
+use chrono::Local;
 use config_file_handler::FileHandler;
-use log::LogRecord;
+use log::{LogLevelFilter, LogRecord};
 use log4rs::append::Append;
-use log4rs::encode::json::JsonEncoder;
 use log4rs::encode::pattern::PatternEncoder;
+use log4rs::encode::writer::console::ConsoleWriter;
 use log4rs::encode::writer::simple::SimpleWriter;
 use log4rs::encode::Encode;
 use log4rs::file::{Deserialize, Deserializers};
-use regex::Regex;
 use serde_value::Value;
 use std::borrow::Borrow;
 use std::collections::BTreeMap;
+use std::env;
 use std::error::Error;
+use std::ffi::OsString;
 use std::fmt::{self, Display, Formatter};
-use std::fs::{File, OpenOptions};
-use std::io::{self, Stdout, Write};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Stderr, Stdout, Write};
 use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
-use std::sync::mpsc::{self, Sender};
-use std::sync::Mutex;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 /// Message terminator for streaming to Log Servers. Servers must look out for this sequence which
 /// demarcates the end of a particular log message.
 pub const MSG_TERMINATOR: [u8; 3] = [254, 253, 255];
 
+/// Environment variable which, if set, overrides the directory that async file appenders configured
+/// via `log.toml` write to.
+pub const LOG_DIR_ENV_VAR: &str = "MAIDSAFE_LOG_DIR";
+
 pub struct AsyncConsoleAppender;
 
 impl AsyncConsoleAppender {
-    pub fn builder() -> AsyncConsoleAppenderBuilder {
-        AsyncConsoleAppenderBuilder {
-            encoder: Box::new(PatternEncoder::default()),
+    pub fn builder() -> AsyncAppenderBuilder<ConsoleSink> {
+        AsyncAppender::builder(ConsoleSink {
+            colour: ColourChoice::Auto,
+            target: ConsoleTarget::Stdout,
+        })
+    }
+}
+
+/// The console stream written to by an async console appender, and whether to colour it.
+pub struct ConsoleSink {
+    colour: ColourChoice,
+    target: ConsoleTarget,
+}
+
+/// The standard stream written to by an async console appender.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConsoleTarget {
+    /// Standard output.
+    Stdout,
+    /// Standard error.
+    Stderr,
+}
+
+impl ConsoleTarget {
+    fn is_tty(self) -> bool {
+        match self {
+            ConsoleTarget::Stdout => ConsoleWriter::stdout().is_some(),
+            ConsoleTarget::Stderr => ConsoleWriter::stderr().is_some(),
         }
     }
 }
 
-pub struct AsyncConsoleAppenderBuilder {
-    encoder: Box<Encode>,
+impl FromStr for ConsoleTarget {
+    type Err = ();
+
+    fn from_str(target: &str) -> Result<Self, ()> {
+        match &target.to_lowercase()[..] {
+            "stdout" => Ok(ConsoleTarget::Stdout),
+            "stderr" => Ok(ConsoleTarget::Stderr),
+            _ => Err(()),
+        }
+    }
+}
+
+/// The format in which an appender encodes records.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    /// Human-readable text as given by a pattern.
+    Pattern,
+    /// One JSON object per line, holding the time (RFC 3339), level, module, file, line, thread
+    /// name and message of the record.
+    Json,
+    /// Compact binary records, see [`BinaryEncoder`](struct.BinaryEncoder.html).
+    Binary,
 }
 
-impl AsyncConsoleAppenderBuilder {
-    pub fn encoder(self, encoder: Box<Encode>) -> Self {
-        AsyncConsoleAppenderBuilder { encoder }
+impl FromStr for Encoding {
+    type Err = ();
+
+    fn from_str(encoding: &str) -> Result<Self, ()> {
+        match &encoding.to_lowercase()[..] {
+            "pattern" => Ok(Encoding::Pattern),
+            "json" => Ok(Encoding::Json),
+            "binary" => Ok(Encoding::Binary),
+            _ => Err(()),
+        }
     }
+}
 
+impl AsyncAppenderBuilder<ConsoleSink> {
+    /// Sets whether records are coloured by level.  Only suitable for text encoders, since the
+    /// escape codes would corrupt e.g. JSON or binary records.
+    pub fn colour(mut self, colour: ColourChoice) -> Self {
+        self.sink.colour = colour;
+        self
+    }
+
+    /// Sets the stream written to. Defaults to stdout.
+    pub fn target(mut self, target: ConsoleTarget) -> Self {
+        self.sink.target = target;
+        self
+    }
+
+    /// Consumes the builder, starting the background thread which writes to the console.
     pub fn build(self) -> AsyncAppender {
-        AsyncAppender::new(io::stdout(), self.encoder)
+        let (sink, mut options) = self.split();
+        if sink.colour.enabled(sink.target.is_tty()) {
+            options.encoder = Box::new(ColourEncoder(options.encoder));
+        }
+        match sink.target {
+            ConsoleTarget::Stdout => {
+                options.name = "console:stdout".to_owned();
+                AsyncAppender::new(io::stdout(), options)
+            }
+            ConsoleTarget::Stderr => {
+                options.name = "console:stderr".to_owned();
+                AsyncAppender::new(io::stderr(), options)
+            }
+        }
+    }
+}
+
+/// Creates an async appender which writes to an arbitrary `Write` sink, flushing after every
+/// record.
+pub struct AsyncWriterAppender;
+
+impl AsyncWriterAppender {
+    /// Returns a builder for an appender writing to `writer`.
+    pub fn builder<W: 'static + Write + Send>(writer: W) -> AsyncAppenderBuilder<WriteSink<W>> {
+        let mut builder = AsyncAppender::builder(WriteSink(writer));
+        builder.name = "writer".to_owned();
+        builder
     }
 }
 
 pub struct AsyncFileAppender;
 
 impl AsyncFileAppender {
-    pub fn builder<P: AsRef<Path>>(path: P) -> AsyncFileAppenderBuilder {
-        AsyncFileAppenderBuilder {
+    pub fn builder<P: AsRef<Path>>(path: P) -> AsyncAppenderBuilder<FileSink> {
+        AsyncAppender::builder(FileSink {
             path: path.as_ref().to_path_buf(),
-            encoder: Box::new(PatternEncoder::default()),
             append: true,
-            timestamp: false,
-        }
+        })
     }
 }
 
-pub struct AsyncFileAppenderBuilder {
+/// The file written to by an async file appender.
+pub struct FileSink {
     path: PathBuf,
-    encoder: Box<Encode>,
     append: bool,
-    timestamp: bool,
 }
 
-impl AsyncFileAppenderBuilder {
-    pub fn encoder(self, encoder: Box<Encode>) -> Self {
-        AsyncFileAppenderBuilder {
-            path: self.path,
-            encoder,
-            append: self.append,
-            timestamp: self.timestamp,
-        }
-    }
-
-    pub fn append(self, append: bool) -> Self {
-        AsyncFileAppenderBuilder {
-            path: self.path,
-            encoder: self.encoder,
-            append,
-            timestamp: self.timestamp,
-        }
-    }
-
-    pub fn timestamp(self, timestamp: bool) -> Self {
-        AsyncFileAppenderBuilder {
-            path: self.path,
-            encoder: self.encoder,
-            append: self.append,
-            timestamp,
-        }
+impl AsyncAppenderBuilder<FileSink> {
+    /// Sets whether to append to an existing file rather than truncating it. Defaults to `true`.
+    pub fn append(mut self, append: bool) -> Self {
+        self.sink.append = append;
+        self
     }
 
+    /// Consumes the builder, opening the file and starting the background thread which writes to
+    /// it.
     pub fn build(self) -> io::Result<AsyncAppender> {
-        let file = if self.append {
+        let (sink, mut options) = self.split();
+        options.name = format!("file:{}", sink.path.display());
+        let file = if sink.append {
             OpenOptions::new()
                 .write(true)
                 .append(true)
                 .create(true)
-                .open(self.path)?
+                .open(sink.path)?
         } else {
             OpenOptions::new()
                 .write(true)
                 .truncate(true)
                 .create(true)
-                .open(self.path)?
+                .open(sink.path)?
         };
 
-        Ok(AsyncAppender::new(file, self.encoder))
+        Ok(AsyncAppender::new(file, options))
     }
 }
 
 pub struct AsyncServerAppender;
 
 impl AsyncServerAppender {
-    pub fn builder<A: ToSocketAddrs>(server_addr: A) -> AsyncServerAppenderBuilder<A> {
-        AsyncServerAppenderBuilder {
+    pub fn builder<A: ToSocketAddrs>(server_addr: A) -> AsyncAppenderBuilder<ServerSink<A>> {
+        AsyncAppender::builder(ServerSink {
             addr: server_addr,
-            encoder: Box::new(PatternEncoder::default()),
             no_delay: true,
-        }
+        })
     }
 }
 
-pub struct AsyncServerAppenderBuilder<A> {
+/// The log server written to by an async server appender.
+pub struct ServerSink<A> {
     addr: A,
-    encoder: Box<Encode>,
     no_delay: bool,
 }
 
-impl<A: ToSocketAddrs> AsyncServerAppenderBuilder<A> {
-    pub fn encoder(self, encoder: Box<Encode>) -> Self {
-        AsyncServerAppenderBuilder {
-            addr: self.addr,
-            encoder,
-            no_delay: self.no_delay,
-        }
-    }
-
-    pub fn no_delay(self, no_delay: bool) -> Self {
-        AsyncServerAppenderBuilder {
-            addr: self.addr,
-            encoder: self.encoder,
-            no_delay,
-        }
+impl<A: ToSocketAddrs> AsyncAppenderBuilder<ServerSink<A>> {
+    /// Sets `TCP_NODELAY` on the connection. Defaults to `true`.
+    pub fn no_delay(mut self, no_delay: bool) -> Self {
+        self.sink.no_delay = no_delay;
+        self
     }
 
+    /// Consumes the builder, connecting to the server and starting the background thread which
+    /// writes to it.
     pub fn build(self) -> io::Result<AsyncAppender> {
-        let stream = TcpStream::connect(self.addr)?;
-        stream.set_nodelay(self.no_delay)?;
-        Ok(AsyncAppender::new(stream, self.encoder))
+        let (sink, mut options) = self.split();
+        let stream = TcpStream::connect(sink.addr)?;
+        stream.set_nodelay(sink.no_delay)?;
+        options.name = format!("server:{}", stream.peer_addr()?);
+        Ok(AsyncAppender::new(stream, options))
     }
 }
 
 pub struct AsyncWebSockAppender;
 
 impl AsyncWebSockAppender {
-    pub fn builder<U: Borrow<str>>(server_url: U) -> AsyncWebSockAppenderBuilder<U> {
-        AsyncWebSockAppenderBuilder {
+    pub fn builder<U: Borrow<str>>(server_url: U) -> AsyncAppenderBuilder<WebSockSink<U>> {
+        AsyncAppender::builder(WebSockSink {
             url: server_url,
             session_id: None,
-            encoder: Box::new(PatternEncoder::default()),
-        }
+        })
     }
 }
 
-pub struct AsyncWebSockAppenderBuilder<U> {
+/// The web socket server written to by an async web socket appender.
+pub struct WebSockSink<U> {
     url: U,
     session_id: Option<String>,
-    encoder: Box<Encode>,
 }
 
-impl<U: Borrow<str>> AsyncWebSockAppenderBuilder<U> {
-    pub fn encoder(mut self, encoder: Box<Encode>) -> Self {
-        self.encoder = encoder;
-        self
-    }
-
+impl<U: Borrow<str>> AsyncAppenderBuilder<WebSockSink<U>> {
+    /// Sets the session ID sent in the handshake with the server. Defaults to none.
     pub fn session_id(mut self, session_id: Option<String>) -> Self {
-        self.session_id = session_id;
+        self.sink.session_id = session_id;
         self
     }
 
+    /// Consumes the builder, starting the background thread which connects to the server and
+    /// writes to it.
     pub fn build(self) -> io::Result<AsyncAppender> {
-        let ws = WebSocket::new(self.url, self.session_id);
-        Ok(AsyncAppender::new(ws, self.encoder))
+        let (sink, mut options) = self.split();
+        options.name = format!("web_socket:{}", sink.url.borrow());
+        let ws = WebSocket::new(sink.url, sink.session_id);
+        Ok(AsyncAppender::new(ws, options))
     }
 }
 
@@ -221,14 +305,27 @@ impl Deserialize for AsyncConsoleAppenderCreator {
             _ => return Err(Box::new(ConfigError("config must be a map".to_owned()))),
         };
 
-        let pattern = parse_pattern(&mut map, false)?;
-        Ok(Box::new(
-            AsyncConsoleAppender::builder().encoder(pattern).build(),
-        ))
+        let colour = parse_colour(&mut map)?;
+
+        let target = match map.remove(&Value::String("target".to_owned())) {
+            Some(Value::String(target)) => target
+                .parse()
+                .map_err(|()| ConfigError("`target` must be either stdout or stderr".to_owned()))?,
+            Some(_) => {
+                return Err(Box::new(ConfigError(
+                    "`target` must be a string".to_owned(),
+                )));
+            }
+            None => ConsoleTarget::Stdout,
+        };
+
+        let builder = parse_common(&mut map, AsyncConsoleAppender::builder(), false)?;
+        Ok(Box::new(builder.colour(colour).target(target).build()))
     }
 }
 
-/// Takes an optional parameter for an output file name override.
+/// Takes an optional parameter for an output file name override, which unlike `output_file_name`
+/// is used as-is, without expanding placeholders.
 pub struct AsyncFileAppenderCreator(pub Option<String>);
 
 impl Deserialize for AsyncFileAppenderCreator {
@@ -240,18 +337,31 @@ impl Deserialize for AsyncFileAppenderCreator {
         config: Value,
         _deserializers: &Deserializers,
     ) -> Result<Box<Append>, Box<Error + Sync + Send>> {
-        use std::time::UNIX_EPOCH;
-
         let mut map = match config {
             Value::Map(map) => map,
             _ => return Err(Box::new(ConfigError("config must be a map".to_owned()))),
         };
 
+        let session_id = match map.remove(&Value::String("session_id".to_owned())) {
+            Some(Value::String(id)) => Some(id),
+            Some(_) => {
+                return Err(Box::new(ConfigError(
+                    "`session_id` must be a string".to_owned(),
+                )));
+            }
+            None => None,
+        };
+
+        // An override given in code is used as-is, without expanding placeholders.
+        let output_file_name = map.remove(&Value::String("output_file_name".to_owned()));
         let mut op_file = if let Some(op_file_name_override) = self.0.clone() {
             op_file_name_override
         } else {
-            match map.remove(&Value::String("output_file_name".to_owned())) {
-                Some(Value::String(op_file)) => op_file,
+            match output_file_name {
+                Some(Value::String(op_file)) => {
+                    file_name::expand(&op_file, session_id.as_ref().map(String::as_str))
+                        .map_err(ConfigError)?
+                }
                 Some(_) => {
                     return Err(Box::new(ConfigError(
                         "`output_file_name` must be a string".to_owned(),
@@ -276,63 +386,122 @@ impl Deserialize for AsyncFileAppenderCreator {
         };
 
         if timestamp {
-            let path = Path::new(&op_file).to_owned();
-            let mut path_owned = path.to_owned();
-            path.file_stem()
-                .and_then(|s| s.to_str())
-                .and_then(|stem| {
-                    UNIX_EPOCH
-                        .elapsed()
-                        .map_err(|e| println!("Could not get timestamp: {:?}", e))
-                        .ok()
-                        .map(|dur| (dur, stem))
-                })
-                .and_then(|elt| {
-                    path.extension()
-                        .and_then(|ex| ex.to_str())
-                        .map(|ex| (elt, ex))
-                })
-                .map_or_else(
-                    || println!("Could not set timestamped file!"),
-                    |((dur, stem), ext)| {
-                        path_owned.set_file_name(format!("{}-{}.{}", stem, dur.as_secs(), ext))
-                    },
-                );
-
-            path_owned.file_name().and_then(|f| f.to_str()).map_or_else(
-                || println!("Could not extract modified file name from path"),
-                |f| op_file = f.to_string(),
-            );
+            let secs = UNIX_EPOCH
+                .elapsed()
+                .map_err(|e| ConfigError(format!("Could not get timestamp: {}", e)))?
+                .as_secs();
+            op_file = timestamped(&op_file, secs)?;
         }
 
-        let op_path = match FileHandler::<()>::new(&op_file, true) {
-            Ok(fh) => fh.path().to_path_buf(),
-            Err(e) => {
-                return Err(Box::new(ConfigError(format!(
-                    "Could not establish log file path: \
-                     {:?}",
-                    e
-                ))));
+        let log_dir = match map.remove(&Value::String("log_dir".to_owned())) {
+            Some(Value::String(dir)) => Some(PathBuf::from(dir)),
+            Some(_) => {
+                return Err(Box::new(ConfigError(
+                    "`log_dir` must be a string".to_owned(),
+                )))
             }
+            None => None,
         };
 
+        let op_path = resolve_log_path(&op_file, log_dir, env::var_os(LOG_DIR_ENV_VAR))?;
+
         let append = match map.remove(&Value::String("append".to_owned())) {
             Some(Value::Bool(append)) => append,
             Some(_) => return Err(Box::new(ConfigError("`append` must be a bool".to_owned()))),
             None => false,
         };
 
-        let pattern = parse_pattern(&mut map, false)?;
-        let appender = AsyncFileAppender::builder(op_path)
-            .encoder(pattern)
+        let appender = parse_common(&mut map, AsyncFileAppender::builder(&op_path), false)?
             .append(append)
-            .timestamp(timestamp)
-            .build()?;
+            .build()
+            .map_err(|e| {
+                ConfigError(format!(
+                    "Could not open log file {}: {}",
+                    op_path.display(),
+                    e
+                ))
+            })?;
 
         Ok(Box::new(appender))
     }
 }
 
+/// Inserts `secs` before the extension of the file name in `op_file`, e.g. `node.log` becomes
+/// `node-1546300800.log`.
+fn timestamped(op_file: &str, secs: u64) -> Result<String, ConfigError> {
+    let path = Path::new(op_file);
+    let invalid = || ConfigError(format!("`{}` is not a valid log file name", op_file));
+    let stem = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .ok_or_else(invalid)?;
+    let file_name = match path.extension() {
+        Some(ext) => format!("{}-{}.{}", stem, secs, ext.to_str().ok_or_else(invalid)?),
+        None => format!("{}-{}", stem, secs),
+    };
+    path.with_file_name(file_name)
+        .into_os_string()
+        .into_string()
+        .map_err(|_| invalid())
+}
+
+/// Works out where a file appender should write to.  The base directory is taken from, in order of
+/// precedence, the `LOG_DIR_ENV_VAR` environment variable or the `log_dir` config key.  A relative
+/// `op_file` is placed under the base directory including any directories it names, while an
+/// absolute one only keeps its file name.  Without a base directory, an absolute `op_file` is used
+/// as-is and a relative one is located by `config_file_handler`.
+///
+/// The parent directory of the resulting path is created if missing, unless it is chosen by
+/// `config_file_handler`.
+fn resolve_log_path(
+    op_file: &str,
+    log_dir: Option<PathBuf>,
+    env_log_dir: Option<OsString>,
+) -> Result<PathBuf, Box<Error + Sync + Send>> {
+    let op_path = Path::new(op_file);
+    let path = match (
+        env_log_dir.map(PathBuf::from).or(log_dir),
+        op_path.is_absolute(),
+    ) {
+        (Some(dir), false) => dir.join(op_path),
+        (Some(dir), true) => {
+            let file_name = op_path.file_name().ok_or_else(|| {
+                ConfigError(format!("`{}` is not a valid log file name", op_file))
+            })?;
+            dir.join(file_name)
+        }
+        (None, true) => op_path.to_path_buf(),
+        (None, false) => {
+            return FileHandler::<()>::new(op_file, true)
+                .map(|fh| fh.path().to_path_buf())
+                .map_err(|e| {
+                    Box::<Error + Sync + Send>::from(ConfigError(format!(
+                        "Could not establish log file path: {:?}",
+                        e
+                    )))
+                });
+        }
+    };
+
+    if path.file_name().is_none() {
+        return Err(Box::new(ConfigError(format!(
+            "`{}` is not a valid log file name",
+            op_file
+        ))));
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| {
+            ConfigError(format!(
+                "Could not create log directory {}: {}",
+                dir.display(),
+                e
+            ))
+        })?;
+    }
+    // Whether the directory is writable is left to opening the file, which reports the path.
+    Ok(path)
+}
+
 pub struct AsyncServerAppenderCreator;
 
 impl Deserialize for AsyncServerAppenderCreator {
@@ -371,14 +540,8 @@ impl Deserialize for AsyncServerAppenderCreator {
             }
             None => true,
         };
-        let pattern = parse_pattern(&mut map, false)?;
-
-        Ok(Box::new(
-            AsyncServerAppender::builder(server_addr)
-                .encoder(pattern)
-                .no_delay(no_delay)
-                .build()?,
-        ))
+        let builder = parse_common(&mut map, AsyncServerAppender::builder(server_addr), false)?;
+        Ok(Box::new(builder.no_delay(no_delay).build()?))
     }
 }
 
@@ -418,32 +581,402 @@ impl Deserialize for AsyncWebSockAppenderCreator {
             None => None,
         };
 
-        let pattern = parse_pattern(&mut map, true)?;
+        let builder = parse_common(&mut map, AsyncWebSockAppender::builder(server_url), true)?;
+        Ok(Box::new(builder.session_id(session_id).build()?))
+    }
+}
+
+/// A `log.toml` deserializer for async appenders writing to a user-defined `SyncWrite` sink.
+///
+/// The wrapped function is given the appender's config map and should remove and parse the keys
+/// it needs to construct the sink.  The keys common to all async appenders are then handled in the
+/// same way as for the built-in ones:
+///
+/// * `encoding`, `pattern`, `timestamp`, `timestamp_zone` and `timestamp_precision` choose the
+///   encoder,
+/// * `threshold` drops records below a level,
+/// * `dedup_window_ms`, `rate_limit_per_second` and `rate_limit_burst` limit repeated or excessive
+///   records,
+/// * `sampling` keeps a proportion of records per level or module, and
+/// * `redact` disables the rules given to [`set_redaction`](fn.set_redaction.html).
+///
+/// Register the creator via [`init_with_deserializers`](fn.init_with_deserializers.html).
+pub struct AsyncAppenderCreator<F>(pub F);
+
+impl<F, S> Deserialize for AsyncAppenderCreator<F>
+where
+    F: Fn(&mut BTreeMap<Value, Value>) -> Result<S, Box<Error + Sync + Send>>
+        + Send
+        + Sync
+        + 'static,
+    S: 'static + SyncWrite + Send,
+{
+    type Trait = Append;
+    type Config = Value;
+
+    fn deserialize(
+        &self,
+        config: Value,
+        _deserializers: &Deserializers,
+    ) -> Result<Box<Append>, Box<Error + Sync + Send>> {
+        let mut map = match config {
+            Value::Map(map) => map,
+            _ => return Err(Box::new(ConfigError("config must be a map".to_owned()))),
+        };
+
+        let sink = (self.0)(&mut map)?;
         Ok(Box::new(
-            AsyncWebSockAppender::builder(server_url)
-                .encoder(pattern)
-                .session_id(session_id)
-                .build()?,
+            parse_common(&mut map, AsyncAppender::builder(sink), false)?.build(),
         ))
     }
 }
 
-fn parse_pattern(
+/// Applies the keys shared by all async appenders to `builder`.
+fn parse_common<S>(
     map: &mut BTreeMap<Value, Value>,
+    builder: AsyncAppenderBuilder<S>,
     is_websocket: bool,
-) -> Result<Box<Encode>, Box<Error + Sync + Send>> {
-    match map.remove(&Value::String("pattern".to_owned())) {
-        Some(Value::String(pattern)) => Ok(Box::new(PatternEncoder::new(&pattern))),
+) -> Result<AsyncAppenderBuilder<S>, Box<Error + Sync + Send>> {
+    Ok(parse_pattern(map, builder, is_websocket)?
+        .threshold(parse_threshold(map)?)
+        .rate_limit(parse_rate_limit(map)?)
+        .sampling(parse_sampling(map)?)
+        .redact(parse_redact(map)?))
+}
+
+/// The log4rs default pattern, with its date replaced by a `{ts}` timestamp.  Used when the
+/// `timestamp` keys are given without a `pattern`.
+const DEFAULT_TIMESTAMPED_PATTERN: &str = "{ts} {l} {t} - {m}{n}";
+
+/// Applies the `encoding`, `pattern` and timestamp keys to `builder`.  The timestamp keys are
+/// rejected for JSON and binary encodings, which always write the full time of each record.
+fn parse_pattern<S>(
+    map: &mut BTreeMap<Value, Value>,
+    builder: AsyncAppenderBuilder<S>,
+    is_websocket: bool,
+) -> Result<AsyncAppenderBuilder<S>, Box<Error + Sync + Send>> {
+    let encoding = match map.remove(&Value::String("encoding".to_owned())) {
+        Some(Value::String(encoding)) => Some(encoding.parse().map_err(|()| {
+            ConfigError("`encoding` must be one of pattern, json or binary".to_owned())
+        })?),
+        Some(_) => {
+            return Err(Box::new(ConfigError(
+                "`encoding` must be a string".to_owned(),
+            )));
+        }
+        None => None,
+    };
+
+    let pattern = match map.remove(&Value::String("pattern".to_owned())) {
+        Some(Value::String(pattern)) => Some(pattern),
+        Some(_) => {
+            return Err(Box::new(ConfigError(
+                "`pattern` must be a string".to_owned(),
+            )));
+        }
+        None => None,
+    };
+
+    let has_timestamp = ["timestamp", "timestamp_zone", "timestamp_precision"]
+        .iter()
+        .any(|key| map.contains_key(&Value::String((*key).to_owned())));
+    let timestamp = parse_timestamp_format(map)?;
+
+    let (encoding, encoder): (_, Box<Encode>) = match (encoding, pattern) {
+        (Some(Encoding::Json), Some(_)) | (Some(Encoding::Binary), Some(_)) => {
+            return Err(Box::new(ConfigError(
+                "`pattern` can only be used with `encoding = \"pattern\"`".to_owned(),
+            )));
+        }
+        (Some(Encoding::Json), None) => (Encoding::Json, Box::new(JsonEncoder::new())),
+        (Some(Encoding::Binary), None) => (Encoding::Binary, Box::new(BinaryEncoder::new())),
+        (_, Some(pattern)) => (
+            Encoding::Pattern,
+            Box::new(pattern::PatternEncoder::with_timestamp(&pattern, timestamp)),
+        ),
+        (None, None) if is_websocket => (Encoding::Json, Box::new(JsonEncoder::new())),
+        (_, None) if has_timestamp => (
+            Encoding::Pattern,
+            Box::new(pattern::PatternEncoder::with_timestamp(
+                DEFAULT_TIMESTAMPED_PATTERN,
+                timestamp,
+            )),
+        ),
+        (_, None) => (Encoding::Pattern, Box::new(PatternEncoder::default())),
+    };
+
+    if has_timestamp && encoding != Encoding::Pattern {
+        return Err(Box::new(ConfigError(
+            "the `timestamp` keys can only be used with `encoding = \"pattern\"`".to_owned(),
+        )));
+    }
+
+    Ok(AsyncAppenderBuilder {
+        encoder,
+        encoding,
+        timestamp,
+        ..builder
+    })
+}
+
+/// Parses the `color` key, which only applies to pattern encodings: escape codes would corrupt
+/// JSON or binary records.
+fn parse_colour(
+    map: &mut BTreeMap<Value, Value>,
+) -> Result<ColourChoice, Box<Error + Sync + Send>> {
+    let colour = match map.remove(&Value::String("color".to_owned())) {
+        Some(Value::String(colour)) => colour
+            .parse()
+            .map_err(|()| ConfigError("`color` must be one of auto, always or never".to_owned()))?,
+        Some(_) => return Err(Box::new(ConfigError("`color` must be a string".to_owned()))),
+        None => ColourChoice::Auto,
+    };
+
+    // An invalid `encoding` is reported by `parse_pattern`.
+    let is_pattern = match map.get(&Value::String("encoding".to_owned())) {
+        Some(&Value::String(ref encoding)) => {
+            encoding.parse().unwrap_or(Encoding::Pattern) == Encoding::Pattern
+        }
+        _ => true,
+    };
+    match (colour, is_pattern) {
+        (_, true) => Ok(colour),
+        (ColourChoice::Always, false) => Err(Box::new(ConfigError(
+            "`color = \"always\"` can only be used with `encoding = \"pattern\"`".to_owned(),
+        ))),
+        (_, false) => Ok(ColourChoice::Never),
+    }
+}
+
+fn parse_timestamp_format(
+    map: &mut BTreeMap<Value, Value>,
+) -> Result<TimestampFormat, Box<Error + Sync + Send>> {
+    let mut format = TimestampFormat::new();
+
+    match map.remove(&Value::String("timestamp".to_owned())) {
+        Some(Value::String(ref timestamp)) if timestamp.eq_ignore_ascii_case("time") => (),
+        Some(Value::String(ref timestamp)) if timestamp.eq_ignore_ascii_case("iso8601") => {
+            format = format.date(true)
+        }
+        Some(_) => {
+            return Err(Box::new(ConfigError(
+                "`timestamp` must be either time or iso8601".to_owned(),
+            )));
+        }
+        None => (),
+    }
+
+    match map.remove(&Value::String("timestamp_zone".to_owned())) {
+        Some(Value::String(ref zone)) if zone.eq_ignore_ascii_case("local") => (),
+        Some(Value::String(ref zone)) if zone.eq_ignore_ascii_case("utc") => {
+            format = format.utc(true)
+        }
+        Some(_) => {
+            return Err(Box::new(ConfigError(
+                "`timestamp_zone` must be either local or utc".to_owned(),
+            )));
+        }
+        None => (),
+    }
+
+    match map.remove(&Value::String("timestamp_precision".to_owned())) {
+        Some(Value::String(precision)) => {
+            format = format.precision(precision.parse().map_err(|()| {
+                ConfigError("`timestamp_precision` must be one of s, ms, us or ns".to_owned())
+            })?)
+        }
+        Some(_) => {
+            return Err(Box::new(ConfigError(
+                "`timestamp_precision` must be a string".to_owned(),
+            )));
+        }
+        None => (),
+    }
+
+    Ok(format)
+}
+
+fn parse_rate_limit(
+    map: &mut BTreeMap<Value, Value>,
+) -> Result<RateLimit, Box<Error + Sync + Send>> {
+    let mut rate_limit = RateLimit::new();
+
+    if let Some(window) = parse_count(map, "dedup_window_ms")? {
+        rate_limit = rate_limit.dedup_window(Duration::from_millis(window));
+    }
+
+    let per_second = parse_count(map, "rate_limit_per_second")?;
+    let burst = parse_count(map, "rate_limit_burst")?;
+    match (per_second, burst) {
+        (Some(per_second), burst) => {
+            let per_second = to_u32(per_second, "rate_limit_per_second")?;
+            let burst = to_u32(
+                burst.unwrap_or_else(|| u64::from(per_second)),
+                "rate_limit_burst",
+            )?;
+            rate_limit = rate_limit.module_rate(per_second, burst);
+        }
+        (None, Some(_)) => {
+            return Err(Box::new(ConfigError(
+                "`rate_limit_burst` requires `rate_limit_per_second`".to_owned(),
+            )));
+        }
+        (None, None) => (),
+    }
+
+    Ok(rate_limit)
+}
+
+fn parse_redact(map: &mut BTreeMap<Value, Value>) -> Result<bool, Box<Error + Sync + Send>> {
+    match map.remove(&Value::String("redact".to_owned())) {
+        Some(Value::Bool(redact)) => Ok(redact),
         Some(_) => Err(Box::new(ConfigError(
-            "`pattern` must be a string".to_owned(),
+            "`redact` must be a boolean".to_owned(),
         ))),
-        None => {
-            if is_websocket {
-                Ok(Box::new(JsonEncoder::new()))
-            } else {
-                Ok(Box::new(PatternEncoder::default()))
+        None => Ok(true),
+    }
+}
+
+fn parse_sampling(map: &mut BTreeMap<Value, Value>) -> Result<Sampling, Box<Error + Sync + Send>> {
+    let rules = match map.remove(&Value::String("sampling".to_owned())) {
+        Some(Value::Seq(rules)) => rules,
+        Some(_) => {
+            return Err(Box::new(ConfigError(
+                "`sampling` must be an array of tables".to_owned(),
+            )));
+        }
+        None => return Ok(Sampling::new()),
+    };
+
+    let mut sampling = Sampling::new();
+    for rule in rules {
+        let mut rule = match rule {
+            Value::Map(rule) => rule,
+            _ => {
+                return Err(Box::new(ConfigError(
+                    "each `sampling` rule must be a table".to_owned(),
+                )));
+            }
+        };
+
+        let module = match rule.remove(&Value::String("module".to_owned())) {
+            Some(Value::String(module)) => module,
+            Some(_) => {
+                return Err(Box::new(ConfigError(
+                    "sampling `module` must be a string".to_owned(),
+                )));
+            }
+            None => String::new(),
+        };
+
+        let level = match rule.remove(&Value::String("level".to_owned())) {
+            Some(Value::String(level)) => level.parse().map_err(|()| {
+                ConfigError(format!(
+                    "sampling `level` must be one of error, warn, info, debug or trace, got `{}`",
+                    level
+                ))
+            })?,
+            _ => {
+                return Err(Box::new(ConfigError(
+                    "each `sampling` rule needs a `level` string".to_owned(),
+                )));
+            }
+        };
+
+        let one_in = parse_count(&mut rule, "one_in")?;
+        let probability = parse_probability(&mut rule)?;
+        let rate = match (one_in, probability) {
+            (Some(one_in), None) => SampleRate::OneIn(to_u32(one_in, "one_in")?),
+            (None, Some(probability)) => SampleRate::Probability(probability),
+            _ => {
+                return Err(Box::new(ConfigError(
+                    "each `sampling` rule needs exactly one of `one_in` or `probability`"
+                        .to_owned(),
+                )));
             }
+        };
+
+        sampling = sampling.rule(module, level, rate);
+    }
+
+    Ok(sampling)
+}
+
+fn parse_probability(
+    map: &mut BTreeMap<Value, Value>,
+) -> Result<Option<f64>, Box<Error + Sync + Send>> {
+    let probability = match map.remove(&Value::String("probability".to_owned())) {
+        Some(Value::F64(probability)) => probability,
+        Some(Value::F32(probability)) => f64::from(probability),
+        // TOML has no implicit conversion, so `probability = 1` is an integer.
+        Some(Value::U8(probability)) => f64::from(probability),
+        Some(Value::U16(probability)) => f64::from(probability),
+        Some(Value::U32(probability)) => f64::from(probability),
+        Some(Value::U64(probability)) => probability as f64,
+        Some(Value::I8(probability)) => f64::from(probability),
+        Some(Value::I16(probability)) => f64::from(probability),
+        Some(Value::I32(probability)) => f64::from(probability),
+        Some(Value::I64(probability)) => probability as f64,
+        Some(_) => {
+            return Err(Box::new(ConfigError(
+                "sampling `probability` must be a number".to_owned(),
+            )));
         }
+        None => return Ok(None),
+    };
+    if probability.is_nan() || probability < 0.0 || probability > 1.0 {
+        return Err(Box::new(ConfigError(
+            "sampling `probability` must be a number from 0.0 to 1.0".to_owned(),
+        )));
+    }
+    Ok(Some(probability))
+}
+
+fn parse_count(
+    map: &mut BTreeMap<Value, Value>,
+    key: &str,
+) -> Result<Option<u64>, Box<Error + Sync + Send>> {
+    let count = match map.remove(&Value::String(key.to_owned())) {
+        Some(Value::U8(count)) => u64::from(count),
+        Some(Value::U16(count)) => u64::from(count),
+        Some(Value::U32(count)) => u64::from(count),
+        Some(Value::U64(count)) => count,
+        Some(Value::I8(count)) if count >= 0 => count as u64,
+        Some(Value::I16(count)) if count >= 0 => count as u64,
+        Some(Value::I32(count)) if count >= 0 => count as u64,
+        Some(Value::I64(count)) if count >= 0 => count as u64,
+        Some(_) => {
+            return Err(Box::new(ConfigError(format!(
+                "`{}` must be a non-negative integer",
+                key
+            ))));
+        }
+        None => return Ok(None),
+    };
+    Ok(Some(count))
+}
+
+fn to_u32(count: u64, key: &str) -> Result<u32, Box<Error + Sync + Send>> {
+    if count > u64::from(u32::max_value()) {
+        return Err(Box::new(ConfigError(format!("`{}` is too large", key))));
+    }
+    Ok(count as u32)
+}
+
+fn parse_threshold(
+    map: &mut BTreeMap<Value, Value>,
+) -> Result<LogLevelFilter, Box<Error + Sync + Send>> {
+    match map.remove(&Value::String("threshold".to_owned())) {
+        Some(Value::String(threshold)) => threshold.parse().map_err(|()| {
+            Box::<Error + Sync + Send>::from(ConfigError(format!(
+                "`threshold` must be one of off, error, warn, info, debug or trace, got `{}`",
+                threshold
+            )))
+        }),
+        Some(_) => Err(Box::new(ConfigError(
+            "`threshold` must be a string".to_owned(),
+        ))),
+        None => Ok(LogLevelFilter::Trace),
     }
 }
 
@@ -464,68 +997,391 @@ impl Display for ConfigError {
 
 enum AsyncEvent {
     Log(Vec<u8>),
+    Flush(Sender<()>),
     Terminate,
 }
 
+/// Name of the background thread of each async appender.
+pub(crate) const WORKER_THREAD_NAME: &str = "AsyncLog";
+
+lazy_static! {
+    /// The queues of the async appenders, used by `flush`.  Holding them weakly lets a dropped
+    /// appender's queue close; the dead entries are pruned whenever the list is used.
+    static ref QUEUES: Mutex<Vec<Weak<Mutex<Sender<AsyncEvent>>>>> = Mutex::new(Vec::new());
+}
+
+/// Blocks until every async appender has written all records queued before this call, or until
+/// `timeout` has elapsed.  Returns `false` if the timeout elapsed first.
+pub fn flush(timeout: Duration) -> bool {
+    let deadline = Instant::now() + timeout;
+    let mut queues = match QUEUES.lock() {
+        Ok(queues) => queues,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+
+    // Queues of dropped appenders are forgotten.
+    let mut acks = Vec::new();
+    queues.retain(|tx| {
+        let tx = match tx.upgrade() {
+            Some(tx) => tx,
+            None => return false,
+        };
+        let (ack_tx, ack_rx) = mpsc::channel();
+        let tx = match tx.lock() {
+            Ok(tx) => tx,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        if tx.send(AsyncEvent::Flush(ack_tx)).is_ok() {
+            acks.push(ack_rx);
+        }
+        true
+    });
+    drop(queues);
+
+    acks.into_iter().all(|ack_rx| {
+        let now = Instant::now();
+        let wait = if deadline > now {
+            deadline - now
+        } else {
+            Duration::from_secs(0)
+        };
+        // A worker which has since terminated has nothing left to write.
+        match ack_rx.recv_timeout(wait) {
+            Ok(()) | Err(RecvTimeoutError::Disconnected) => true,
+            Err(RecvTimeoutError::Timeout) => false,
+        }
+    })
+}
+
+/// An appender which encodes records on the logging thread and hands them to a dedicated
+/// background thread for writing, so that slow sinks do not block the caller.
 #[derive(Debug)]
 pub struct AsyncAppender {
     encoder: Box<Encode>,
-    tx: Mutex<Sender<AsyncEvent>>,
+    encoding: Encoding,
+    timestamp: TimestampFormat,
+    threshold: LogLevelFilter,
+    sampling: Option<Mutex<Sampling>>,
+    rate_limiter: Option<Mutex<RateLimiter>>,
+    counters: Arc<Counters>,
+    tx: Arc<Mutex<Sender<AsyncEvent>>>,
     _raii_joiner: Joiner,
 }
 
 impl AsyncAppender {
-    fn new<W: 'static + SyncWrite + Send>(mut writer: W, encoder: Box<Encode>) -> Self {
-        let (tx, rx) = mpsc::channel::<AsyncEvent>();
+    /// Returns a builder for an appender writing to a user-defined `sink`.
+    pub fn builder<S>(sink: S) -> AsyncAppenderBuilder<S> {
+        AsyncAppenderBuilder {
+            sink,
+            name: "custom".to_owned(),
+            encoder: Box::new(PatternEncoder::default()),
+            encoding: Encoding::Pattern,
+            timestamp: TimestampFormat::new(),
+            threshold: LogLevelFilter::Trace,
+            rate_limit: RateLimit::new(),
+            sampling: Sampling::new(),
+            redact: true,
+        }
+    }
 
-        let joiner = thread::named("AsyncLog", move || {
-            let re = unwrap!(Regex::new(r"#FS#?.*[/\\#]([^#]+)#FE#"));
+    fn new<W: 'static + SyncWrite + Send>(
+        mut writer: W,
+        options: AsyncAppenderBuilder<()>,
+    ) -> Self {
+        let AsyncAppenderBuilder {
+            sink: (),
+            name,
+            encoder,
+            encoding,
+            timestamp,
+            threshold,
+            rate_limit,
+            sampling,
+            redact,
+        } = options;
+        // Masking bytes of a binary record could corrupt its numbers and lengths.
+        let redact = redact && encoding != Encoding::Binary;
+        let (tx, rx) = mpsc::channel::<AsyncEvent>();
+        let counters = Counters::register(name);
 
+        let worker_counters = Arc::clone(&counters);
+        let joiner = thread::named(WORKER_THREAD_NAME, move || {
             for event in rx.iter() {
                 match event {
                     AsyncEvent::Log(mut msg) => {
-                        if let Ok(mut str_msg) = String::from_utf8(msg) {
-                            let str_msg_cloned = str_msg.clone();
-                            if let Some(file_name_capture) = re.captures(&str_msg_cloned) {
-                                if let Some(file_name) = file_name_capture.get(1) {
-                                    str_msg = re.replace(&str_msg[..], file_name.as_str()).into();
-                                }
-                            }
-
-                            msg = str_msg.into_bytes();
-                            let _ = writer.sync_write(&msg);
+                        if redact {
+                            redaction::redact(&mut msg);
+                        }
+                        match writer.sync_write(&msg) {
+                            Ok(()) => worker_counters.written(msg.len()),
+                            Err(_) => worker_counters.failed(),
                         }
+                        worker_counters.set_reconnect_attempts(writer.reconnect_attempts());
+                    }
+                    AsyncEvent::Flush(ack) => {
+                        let _ = ack.send(());
                     }
                     AsyncEvent::Terminate => break,
                 }
             }
         });
+        let tx = Arc::new(Mutex::new(tx));
+        let mut queues = match QUEUES.lock() {
+            Ok(queues) => queues,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        queues.retain(|tx| tx.upgrade().is_some());
+        queues.push(Arc::downgrade(&tx));
+        drop(queues);
+
+        let sampling = if sampling.is_enabled() {
+            Some(Mutex::new(sampling))
+        } else {
+            None
+        };
+        let rate_limiter = if rate_limit.is_enabled() {
+            Some(Mutex::new(RateLimiter::new(rate_limit)))
+        } else {
+            None
+        };
 
         AsyncAppender {
             encoder,
-            tx: Mutex::new(tx),
+            encoding,
+            timestamp,
+            threshold,
+            sampling,
+            rate_limiter,
+            counters,
+            tx,
             _raii_joiner: joiner,
         }
     }
 }
 
-impl Append for AsyncAppender {
-    fn append(&self, record: &LogRecord) -> Result<(), Box<Error + Sync + Send>> {
+impl AsyncAppender {
+    /// Encodes a summary of suppressed records.  Only the `log` macros can create a `LogRecord` to
+    /// pass to the encoder, so the summary is written here in the appender's encoding, using the
+    /// layout of the `init*` functions for text.
+    fn encode_summary(
+        &self,
+        summary: &Summary,
+        msg: &mut Vec<u8>,
+    ) -> Result<(), Box<Error + Sync + Send>> {
+        match self.encoding {
+            Encoding::Pattern => writeln!(
+                msg,
+                "{} {} [{} {}:{}] {}",
+                summary.level,
+                self.timestamp.format(Local::now()),
+                summary.module,
+                pattern::file_name(&summary.file),
+                summary.line,
+                summary.message
+            )?,
+            Encoding::Json => JsonRecord {
+                time: Local::now(),
+                message: &summary.message,
+                module_path: &summary.module,
+                file: &summary.file,
+                line: summary.line,
+                level: summary.level,
+                target: &summary.target,
+                thread: None,
+            }
+            .write(msg)?,
+            Encoding::Binary => msg.extend(
+                BinaryRecord {
+                    level: summary.level,
+                    time: SystemTime::now(),
+                    module: summary.module.clone(),
+                    file: summary.file.clone(),
+                    line: summary.line,
+                    thread: None,
+                    message: summary.message.clone(),
+                }
+                .serialise()?,
+            ),
+        }
+        Ok(())
+    }
+
+    /// Encodes a record via `encode` and queues it for the background thread.
+    fn encode_and_send<F>(&self, encode: F) -> Result<(), Box<Error + Sync + Send>>
+    where
+        F: FnOnce(&mut Vec<u8>) -> Result<(), Box<Error + Sync + Send>>,
+    {
         let mut msg = Vec::new();
-        self.encoder.encode(&mut SimpleWriter(&mut msg), record)?;
-        unwrap!(self.tx.lock()).send(AsyncEvent::Log(msg))?;
+        if let Err(error) = encode(&mut msg) {
+            // The record never reaches the queue, so it mustn't count against the queue depth.
+            self.counters.dropped();
+            return Err(error);
+        }
+        if let Err(error) = unwrap!(self.tx.lock()).send(AsyncEvent::Log(msg)) {
+            self.counters.dropped();
+            return Err(Box::new(error));
+        }
+        self.counters.enqueued();
         Ok(())
     }
 }
 
+impl Append for AsyncAppender {
+    fn append(&self, record: &LogRecord) -> Result<(), Box<Error + Sync + Send>> {
+        // Drop records below this appender's threshold before paying for the encoding.
+        if record.level() > self.threshold {
+            return Ok(());
+        }
+
+        if let Some(ref sampling) = self.sampling {
+            if !unwrap!(sampling.lock()).keep(record.level(), facade::module_path(record)) {
+                self.counters.dropped();
+                return Ok(());
+            }
+        }
+
+        if let Some(ref rate_limiter) = self.rate_limiter {
+            let site = CallSite {
+                level: record.level(),
+                target: record.target(),
+                module: facade::module_path(record),
+                file: record.location().file(),
+                line: record.location().line(),
+            };
+            let mut summaries = Vec::new();
+            let log = unwrap!(rate_limiter.lock()).check(
+                &site,
+                || record.args().to_string(),
+                Instant::now(),
+                &mut summaries,
+            );
+            for summary in summaries {
+                // A summary which can't be encoded is counted as dropped, without holding up the
+                // record.
+                let _ = self.encode_and_send(|msg| self.encode_summary(&summary, msg));
+            }
+            if !log {
+                self.counters.dropped();
+                return Ok(());
+            }
+        }
+
+        self.encode_and_send(|msg| self.encoder.encode(&mut SimpleWriter(msg), record))
+    }
+}
+
+/// Builder for an async appender, holding the options common to all of them.
+///
+/// `S` is either a user-defined `SyncWrite` sink, or the settings from which one of the built-in
+/// appenders opens its sink when built.
+pub struct AsyncAppenderBuilder<S> {
+    sink: S,
+    name: String,
+    encoder: Box<Encode>,
+    encoding: Encoding,
+    // Format of the timestamps of summaries written as text.
+    timestamp: TimestampFormat,
+    threshold: LogLevelFilter,
+    rate_limit: RateLimit,
+    sampling: Sampling,
+    redact: bool,
+}
+
+impl<S> AsyncAppenderBuilder<S> {
+    /// Sets the encoder used to format records. Defaults to the log4rs `PatternEncoder` default.
+    pub fn encoder(mut self, encoder: Box<Encode>) -> Self {
+        self.encoder = encoder;
+        self
+    }
+
+    /// Sets the encoding of the records written by the encoder, which summaries of records
+    /// suppressed by the [`rate_limit`](#method.rate_limit) are written in, and which disables
+    /// redaction if binary.  Defaults to `Encoding::Pattern`.
+    pub fn encoding(mut self, encoding: Encoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Sets the level below which records are dropped by this appender. Defaults to `Trace`.
+    pub fn threshold(mut self, threshold: LogLevelFilter) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Sets the limits on repeated or excessive records. Defaults to no limits.
+    pub fn rate_limit(mut self, rate_limit: RateLimit) -> Self {
+        self.rate_limit = rate_limit;
+        self
+    }
+
+    /// Sets the sampling applied to records before they are encoded. Defaults to keeping all.
+    pub fn sampling(mut self, sampling: Sampling) -> Self {
+        self.sampling = sampling;
+        self
+    }
+
+    /// Sets whether the rules given to [`set_redaction`](fn.set_redaction.html) are applied to
+    /// this appender's output. Defaults to `true`, but binary records are never redacted.
+    pub fn redact(mut self, redact: bool) -> Self {
+        self.redact = redact;
+        self
+    }
+
+    /// Separates the sink from the remaining options.
+    fn split(self) -> (S, AsyncAppenderBuilder<()>) {
+        let AsyncAppenderBuilder {
+            sink,
+            name,
+            encoder,
+            encoding,
+            timestamp,
+            threshold,
+            rate_limit,
+            sampling,
+            redact,
+        } = self;
+        let options = AsyncAppenderBuilder {
+            sink: (),
+            name,
+            encoder,
+            encoding,
+            timestamp,
+            threshold,
+            rate_limit,
+            sampling,
+            redact,
+        };
+        (sink, options)
+    }
+}
+
+impl<S: 'static + SyncWrite + Send> AsyncAppenderBuilder<S> {
+    /// Consumes the builder, starting the background thread which writes to the sink.
+    pub fn build(self) -> AsyncAppender {
+        let (sink, options) = self.split();
+        AsyncAppender::new(sink, options)
+    }
+}
+
 impl Drop for AsyncAppender {
     fn drop(&mut self) {
         let _ = unwrap!(self.tx.lock()).send(AsyncEvent::Terminate);
     }
 }
 
-trait SyncWrite {
+/// A sink for encoded log records, driven from the background thread of an `AsyncAppender`.
+///
+/// Each call receives exactly one encoded record and should not return until it has been fully
+/// written, so implementations will usually flush or otherwise delimit the record before returning.
+pub trait SyncWrite {
+    /// Writes a single encoded record.
     fn sync_write(&mut self, buf: &[u8]) -> io::Result<()>;
+
+    /// Returns how often reconnecting to a remote sink has been attempted, for
+    /// [`stats`](fn.stats.html).  Defaults to zero for sinks which never reconnect.
+    fn reconnect_attempts(&self) -> usize {
+        0
+    }
 }
 
 impl SyncWrite for Stdout {
@@ -536,6 +1392,14 @@ impl SyncWrite for Stdout {
     }
 }
 
+impl SyncWrite for Stderr {
+    fn sync_write(&mut self, buf: &[u8]) -> io::Result<()> {
+        let mut out = self.lock();
+        out.write_all(buf)?;
+        out.flush()
+    }
+}
+
 impl SyncWrite for File {
     fn sync_write(&mut self, buf: &[u8]) -> io::Result<()> {
         self.write_all(buf)?;
@@ -554,4 +1418,464 @@ impl SyncWrite for WebSocket {
     fn sync_write(&mut self, buf: &[u8]) -> io::Result<()> {
         self.write_all(buf)
     }
+
+    fn reconnect_attempts(&self) -> usize {
+        WebSocket::reconnect_attempts(self)
+    }
+}
+
+/// Adapts a `Write` to a `SyncWrite` sink, flushing after every record.
+pub struct WriteSink<W>(pub W);
+
+impl<W: Write> SyncWrite for WriteSink<W> {
+    fn sync_write(&mut self, buf: &[u8]) -> io::Result<()> {
+        self.0.write_all(buf)?;
+        self.0.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::log::binary::BinaryRecords;
+    use crate::log::stats;
+    use crate::log::timestamp::TimestampPrecision;
+    use log::LogLevel;
+    use std::io::Read;
+    use std::net::TcpListener;
+
+    fn config(entries: &[(&str, Value)]) -> BTreeMap<Value, Value> {
+        entries
+            .iter()
+            .map(|&(key, ref value)| (Value::String(key.to_owned()), value.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn encode_summaries() {
+        let summary = Summary {
+            level: LogLevel::Warn,
+            target: "routing".to_owned(),
+            module: "routing::core".to_owned(),
+            file: "src/core.rs".to_owned(),
+            line: 42,
+            message: "message repeated 2 times: a".to_owned(),
+        };
+        let encode = |encoding| {
+            let appender = AsyncAppender::builder(WriteSink(io::sink()))
+                .encoding(encoding)
+                .build();
+            let mut msg = Vec::new();
+            unwrap!(appender.encode_summary(&summary, &mut msg));
+            msg
+        };
+
+        let text = unwrap!(String::from_utf8(encode(Encoding::Pattern)));
+        assert!(text.starts_with("WARN "));
+        assert!(text.ends_with(" [routing::core core.rs:42] message repeated 2 times: a\n"));
+
+        let json: serde_json::Value = unwrap!(serde_json::from_slice(&encode(Encoding::Json)));
+        assert_eq!(json["module_path"], "routing::core");
+        assert_eq!(json["file"], "src/core.rs");
+        assert_eq!(json["line"], 42);
+        assert_eq!(json["level"], "WARN");
+        assert_eq!(json["target"], "routing");
+        assert_eq!(json["message"], "message repeated 2 times: a");
+
+        let binary = encode(Encoding::Binary);
+        let mut records = BinaryRecords::new(&binary[..]);
+        let record = unwrap!(unwrap!(records.next()));
+        assert_eq!(
+            (
+                record.level,
+                &record.module[..],
+                &record.file[..],
+                record.line
+            ),
+            (LogLevel::Warn, "routing::core", "src/core.rs", 42)
+        );
+        assert_eq!(record.message, summary.message);
+        assert!(records.next().is_none());
+    }
+
+    #[test]
+    fn count_records_sent_to_a_closed_queue() {
+        let mut builder = AsyncAppender::builder(WriteSink(io::sink()));
+        builder.name = "test:closed_queue".to_owned();
+        let appender = builder.build();
+        let _ = unwrap!(appender.tx.lock()).send(AsyncEvent::Terminate);
+
+        // The background thread stops once it has received `Terminate`.
+        let mut sent = 0;
+        while appender
+            .encode_and_send(|msg| {
+                msg.push(b'x');
+                Ok(())
+            })
+            .is_ok()
+        {
+            sent += 1;
+            std::thread::sleep(Duration::from_millis(1));
+        }
+
+        let stats = unwrap!(stats()
+            .into_iter()
+            .find(|stats| stats.sink == "test:closed_queue"));
+        assert_eq!((stats.enqueued, stats.dropped), (sent, 1));
+    }
+
+    #[test]
+    fn parse_console_target() {
+        assert_eq!(
+            unwrap!("stdout".parse::<ConsoleTarget>()),
+            ConsoleTarget::Stdout
+        );
+        assert_eq!(
+            unwrap!("StdErr".parse::<ConsoleTarget>()),
+            ConsoleTarget::Stderr
+        );
+        assert!("stdin".parse::<ConsoleTarget>().is_err());
+    }
+
+    #[test]
+    fn timestamped_file_names() {
+        assert_eq!(unwrap!(timestamped("node.log", 42)), "node-42.log");
+        assert_eq!(unwrap!(timestamped("node", 42)), "node-42");
+        assert_eq!(
+            unwrap!(timestamped("logs/node.tar.gz", 42)),
+            Path::new("logs").join("node.tar-42.gz").to_string_lossy()
+        );
+        assert!(timestamped("", 42).is_err());
+        assert!(timestamped("..", 42).is_err());
+    }
+
+    #[test]
+    fn resolve_absolute_and_directory_relative_paths() {
+        let base = env::temp_dir().join(format!("maidsafe_utilities_log_{}", std::process::id()));
+        let absolute = base.join("absolute.log");
+        let absolute = unwrap!(absolute.to_str());
+
+        // The directory of an absolute path is created too.
+        let nested = base.join("nested").join("absolute.log");
+        let nested = unwrap!(nested.to_str());
+        assert_eq!(
+            unwrap!(resolve_log_path(nested, None, None)),
+            Path::new(nested)
+        );
+        assert!(base.join("nested").is_dir());
+
+        // Relative paths keep their directories.
+        let relative = Path::new("logs").join("node.log");
+        let relative = unwrap!(relative.to_str());
+        assert_eq!(
+            unwrap!(resolve_log_path(relative, Some(base.join("dir")), None)),
+            base.join("dir").join("logs").join("node.log")
+        );
+        assert!(base.join("dir").join("logs").is_dir());
+        assert_eq!(
+            unwrap!(resolve_log_path(
+                relative,
+                Some(base.join("dir")),
+                Some(base.join("env").into_os_string()),
+            )),
+            base.join("env").join("logs").join("node.log")
+        );
+        assert_eq!(
+            unwrap!(resolve_log_path(
+                absolute,
+                Some(base.join("dir")),
+                Some(base.join("env").into_os_string()),
+            )),
+            base.join("env").join("absolute.log")
+        );
+        assert!(base.join("env").is_dir());
+
+        // A log directory which can't be created as its parent is a file.
+        let file_as_dir = base.join("absolute.log").join("dir");
+        let _ = unwrap!(File::create(base.join("absolute.log")));
+        assert!(resolve_log_path("node.log", Some(file_as_dir), None).is_err());
+
+        unwrap!(fs::remove_dir_all(base));
+    }
+
+    #[test]
+    fn parse_encoding() {
+        let mut map = config(&[("encoding", Value::String("json".to_owned()))]);
+        let builder = unwrap!(parse_pattern(&mut map, AsyncAppender::builder(()), false));
+        assert_eq!(
+            format!("{:?}", builder.encoder),
+            format!("{:?}", JsonEncoder::new())
+        );
+        assert_eq!(builder.encoding, Encoding::Json);
+        assert!(map.is_empty());
+
+        let mut map = config(&[]);
+        let builder = unwrap!(parse_pattern(&mut map, AsyncAppender::builder(()), true));
+        assert_eq!(
+            format!("{:?}", builder.encoder),
+            format!("{:?}", JsonEncoder::new())
+        );
+        assert_eq!(builder.encoding, Encoding::Json);
+
+        let mut map = config(&[("encoding", Value::String("binary".to_owned()))]);
+        let builder = unwrap!(parse_pattern(&mut map, AsyncAppender::builder(()), true));
+        assert_eq!(builder.encoding, Encoding::Binary);
+
+        let mut map = config(&[
+            ("encoding", Value::String("json".to_owned())),
+            ("pattern", Value::String("{m}".to_owned())),
+        ]);
+        assert!(parse_pattern(&mut map, AsyncAppender::builder(()), false).is_err());
+
+        let mut map = config(&[("encoding", Value::String("xml".to_owned()))]);
+        assert!(parse_pattern(&mut map, AsyncAppender::builder(()), false).is_err());
+    }
+
+    #[test]
+    fn timestamp_without_pattern() {
+        let mut map = config(&[("timestamp_zone", Value::String("utc".to_owned()))]);
+        let builder = unwrap!(parse_pattern(&mut map, AsyncAppender::builder(()), false));
+        let timestamp = TimestampFormat::new().utc(true);
+        assert_eq!(
+            format!("{:?}", builder.encoder),
+            format!(
+                "{:?}",
+                pattern::PatternEncoder::with_timestamp(DEFAULT_TIMESTAMPED_PATTERN, timestamp)
+            )
+        );
+        assert_eq!(builder.encoding, Encoding::Pattern);
+        assert_eq!(builder.timestamp, timestamp);
+        assert!(map.is_empty());
+
+        // JSON and binary records, including the default for web sockets, ignore the format.
+        let mut map = config(&[
+            ("encoding", Value::String("json".to_owned())),
+            ("timestamp", Value::String("iso8601".to_owned())),
+        ]);
+        assert!(parse_pattern(&mut map, AsyncAppender::builder(()), false).is_err());
+
+        let mut map = config(&[("timestamp_precision", Value::String("ms".to_owned()))]);
+        assert!(parse_pattern(&mut map, AsyncAppender::builder(()), true).is_err());
+    }
+
+    #[test]
+    fn colour_only_applies_to_patterns() {
+        let mut map = config(&[("color", Value::String("always".to_owned()))]);
+        assert_eq!(unwrap!(parse_colour(&mut map)), ColourChoice::Always);
+        assert!(map.is_empty());
+
+        let mut map = config(&[("encoding", Value::String("json".to_owned()))]);
+        assert_eq!(unwrap!(parse_colour(&mut map)), ColourChoice::Never);
+        assert_eq!(map.len(), 1);
+
+        let mut map = config(&[
+            ("color", Value::String("auto".to_owned())),
+            ("encoding", Value::String("binary".to_owned())),
+        ]);
+        assert_eq!(unwrap!(parse_colour(&mut map)), ColourChoice::Never);
+
+        let mut map = config(&[
+            ("color", Value::String("always".to_owned())),
+            ("encoding", Value::String("json".to_owned())),
+        ]);
+        assert!(parse_colour(&mut map).is_err());
+    }
+
+    #[test]
+    fn parse_timestamp() {
+        let mut map = config(&[]);
+        assert_eq!(
+            unwrap!(parse_timestamp_format(&mut map)),
+            TimestampFormat::new()
+        );
+
+        let mut map = config(&[
+            ("timestamp", Value::String("ISO8601".to_owned())),
+            ("timestamp_zone", Value::String("Utc".to_owned())),
+            ("timestamp_precision", Value::String("ms".to_owned())),
+        ]);
+        assert_eq!(
+            unwrap!(parse_timestamp_format(&mut map)),
+            TimestampFormat::new()
+                .date(true)
+                .utc(true)
+                .precision(TimestampPrecision::Millis)
+        );
+        assert!(map.is_empty());
+
+        let mut map = config(&[("timestamp_zone", Value::String("gmt".to_owned()))]);
+        assert!(parse_timestamp_format(&mut map).is_err());
+        let mut map = config(&[("timestamp_precision", Value::Bool(true))]);
+        assert!(parse_timestamp_format(&mut map).is_err());
+
+        let mut map = config(&[
+            ("pattern", Value::String("{ts} {m}".to_owned())),
+            ("timestamp", Value::String("iso8601".to_owned())),
+        ]);
+        assert!(parse_pattern(&mut map, AsyncAppender::builder(()), false).is_ok());
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn parse_rate_limits() {
+        let mut map = config(&[]);
+        assert_eq!(unwrap!(parse_rate_limit(&mut map)), RateLimit::new());
+
+        let mut map = config(&[
+            ("dedup_window_ms", Value::I64(500)),
+            ("rate_limit_per_second", Value::I64(100)),
+        ]);
+        assert_eq!(
+            unwrap!(parse_rate_limit(&mut map)),
+            RateLimit::new()
+                .dedup_window(Duration::from_millis(500))
+                .module_rate(100, 100)
+        );
+        assert!(map.is_empty());
+
+        let mut map = config(&[
+            ("rate_limit_per_second", Value::I64(10)),
+            ("rate_limit_burst", Value::I64(50)),
+        ]);
+        assert_eq!(
+            unwrap!(parse_rate_limit(&mut map)),
+            RateLimit::new().module_rate(10, 50)
+        );
+
+        let mut map = config(&[("rate_limit_burst", Value::I64(50))]);
+        assert!(parse_rate_limit(&mut map).is_err());
+        let mut map = config(&[("dedup_window_ms", Value::I64(-1))]);
+        assert!(parse_rate_limit(&mut map).is_err());
+        let mut map = config(&[("rate_limit_per_second", Value::String("10".to_owned()))]);
+        assert!(parse_rate_limit(&mut map).is_err());
+    }
+
+    #[test]
+    fn parse_sampling_rules() {
+        let mut map = config(&[]);
+        assert!(!unwrap!(parse_sampling(&mut map)).is_enabled());
+
+        let rule = |entries: &[(&str, Value)]| Value::Map(config(entries));
+        let mut map = config(&[(
+            "sampling",
+            Value::Seq(vec![
+                rule(&[
+                    ("module", Value::String("routing".to_owned())),
+                    ("level", Value::String("trace".to_owned())),
+                    ("one_in", Value::I64(10)),
+                ]),
+                rule(&[
+                    ("level", Value::String("debug".to_owned())),
+                    ("probability", Value::F64(0.5)),
+                ]),
+                rule(&[
+                    ("level", Value::String("info".to_owned())),
+                    ("probability", Value::I64(1)),
+                ]),
+            ]),
+        )]);
+        assert!(unwrap!(parse_sampling(&mut map)).is_enabled());
+        assert!(map.is_empty());
+
+        let invalid_rules = vec![
+            rule(&[("one_in", Value::I64(10))]),
+            rule(&[("level", Value::String("trace".to_owned()))]),
+            rule(&[
+                ("level", Value::String("trace".to_owned())),
+                ("one_in", Value::I64(10)),
+                ("probability", Value::F64(0.5)),
+            ]),
+            rule(&[
+                ("level", Value::String("trace".to_owned())),
+                ("probability", Value::F64(1.5)),
+            ]),
+            rule(&[
+                ("level", Value::String("trace".to_owned())),
+                ("probability", Value::F64(std::f64::NAN)),
+            ]),
+            rule(&[
+                ("level", Value::String("trace".to_owned())),
+                ("probability", Value::I64(2)),
+            ]),
+            Value::String("trace".to_owned()),
+        ];
+        for invalid_rule in invalid_rules {
+            let mut map = config(&[("sampling", Value::Seq(vec![invalid_rule]))]);
+            assert!(parse_sampling(&mut map).is_err());
+        }
+    }
+
+    #[test]
+    fn probability_errors() {
+        let error = |probability| {
+            let mut map = config(&[("probability", probability)]);
+            unwrap_err!(parse_probability(&mut map)).to_string()
+        };
+        let not_a_number = "sampling `probability` must be a number";
+        let out_of_range = "sampling `probability` must be a number from 0.0 to 1.0";
+
+        assert_eq!(error(Value::String("0.5".to_owned())), not_a_number);
+        assert_eq!(error(Value::Bool(true)), not_a_number);
+        assert_eq!(error(Value::F64(std::f64::NAN)), out_of_range);
+        assert_eq!(error(Value::F64(-0.1)), out_of_range);
+        assert_eq!(error(Value::I64(2)), out_of_range);
+    }
+
+    #[test]
+    fn threshold_defaults_to_trace() {
+        let mut map = config(&[]);
+        assert_eq!(unwrap!(parse_threshold(&mut map)), LogLevelFilter::Trace);
+    }
+
+    #[test]
+    fn threshold_is_parsed_and_removed() {
+        let mut map = config(&[("threshold", Value::String("warn".to_owned()))]);
+        assert_eq!(unwrap!(parse_threshold(&mut map)), LogLevelFilter::Warn);
+        assert!(map.is_empty());
+
+        let mut map = config(&[("threshold", Value::String("DEBUG".to_owned()))]);
+        assert_eq!(unwrap!(parse_threshold(&mut map)), LogLevelFilter::Debug);
+    }
+
+    #[test]
+    fn custom_sink_creator() {
+        struct NullSink;
+
+        impl SyncWrite for NullSink {
+            fn sync_write(&mut self, _buf: &[u8]) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let creator = AsyncAppenderCreator(|map: &mut BTreeMap<Value, Value>| {
+            match map.remove(&Value::String("pipe".to_owned())) {
+                Some(Value::String(_)) => Ok(NullSink),
+                _ => Err(Box::<Error + Sync + Send>::from(ConfigError(
+                    "`pipe` is required".to_owned(),
+                ))),
+            }
+        });
+
+        let map = config(&[
+            ("pipe", Value::String("sidecar".to_owned())),
+            ("threshold", Value::String("info".to_owned())),
+        ]);
+        assert!(creator
+            .deserialize(Value::Map(map), &Deserializers::default())
+            .is_ok());
+
+        let map = config(&[("threshold", Value::String("info".to_owned()))]);
+        assert!(creator
+            .deserialize(Value::Map(map), &Deserializers::default())
+            .is_err());
+    }
+
+    #[test]
+    fn threshold_rejects_invalid_values() {
+        let mut map = config(&[("threshold", Value::String("loud".to_owned()))]);
+        assert!(parse_threshold(&mut map).is_err());
+
+        let mut map = config(&[("threshold", Value::Bool(true))]);
+        assert!(parse_threshold(&mut map).is_err());
+    }
 }
@@ -0,0 +1,316 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use crate::log::facade;
+use crate::log::pattern;
+use crate::log::timestamp::TimestampFormat;
+use crate::serialisation::{self, SerialisationError};
+use bincode::Bounded;
+use chrono::{Local, TimeZone};
+use log::{LogLevel, LogRecord};
+use log4rs::encode::{self, Encode};
+use std::borrow::Cow;
+use std::error::Error;
+use std::io::{self, BufRead, BufReader, Read};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Upper limit on the size of a single encoded record, to guard against corrupt input.
+const MAX_RECORD_SIZE: u64 = 1024 * 1024;
+
+quick_error! {
+    /// Error reading a binary log.
+    #[derive(Debug)]
+    pub enum BinaryLogError {
+        /// I/O error while reading the log.
+        Io(err: io::Error) {
+            description("I/O error")
+            display("I/O error: {}", err)
+            cause(err)
+            from()
+        }
+        /// A record could not be deserialised.
+        Serialisation(err: SerialisationError) {
+            description("Serialisation error")
+            display("Serialisation error: {}", err)
+            cause(err)
+            from()
+        }
+        /// A record holds an invalid level.
+        InvalidLevel(level: u8) {
+            description("Invalid level")
+            display("Invalid level: {}", level)
+        }
+    }
+}
+
+/// An encoder writing each record in a compact binary form using
+/// [`serialisation::serialise`](../serialisation/fn.serialise.html), which is cheaper to produce
+/// than formatted text.  Such logs can be read back via
+/// [`BinaryRecords`](struct.BinaryRecords.html).
+#[derive(Debug, Default)]
+pub struct BinaryEncoder;
+
+impl BinaryEncoder {
+    /// Creates a new binary encoder.
+    pub fn new() -> Self {
+        BinaryEncoder
+    }
+}
+
+impl Encode for BinaryEncoder {
+    fn encode(
+        &self,
+        w: &mut encode::Write,
+        record: &LogRecord,
+    ) -> Result<(), Box<Error + Sync + Send>> {
+        let since_epoch = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_else(|_| Duration::from_secs(0));
+        let thread = thread::current();
+        let wire_record = WireRecord {
+            level: record.level() as u8,
+            secs: since_epoch.as_secs(),
+            nanos: since_epoch.subsec_nanos(),
+            module: Cow::Borrowed(facade::module_path(record)),
+            file: Cow::Borrowed(record.location().file()),
+            line: record.location().line(),
+            thread: thread.name().map(Cow::Borrowed),
+            message: Cow::Owned(record.args().to_string()),
+        };
+        w.write_all(&serialisation::serialise(&wire_record)?)?;
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct WireRecord<'a> {
+    level: u8,
+    secs: u64,
+    nanos: u32,
+    module: Cow<'a, str>,
+    file: Cow<'a, str>,
+    line: u32,
+    thread: Option<Cow<'a, str>>,
+    message: Cow<'a, str>,
+}
+
+/// A record read back from a binary log.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BinaryRecord {
+    /// Level of the record.
+    pub level: LogLevel,
+    /// Time at which the record was encoded.
+    pub time: SystemTime,
+    /// Module path of the log statement.
+    pub module: String,
+    /// Source file of the log statement.
+    pub file: String,
+    /// Line number of the log statement.
+    pub line: u32,
+    /// Name of the logging thread, if it was named.
+    pub thread: Option<String>,
+    /// The formatted message.
+    pub message: String,
+}
+
+impl BinaryRecord {
+    /// Formats the record in the same way as the text logs produced by the `init*` functions of
+    /// this module with the given timestamp format, including the trailing newline.
+    pub fn to_text(&self, timestamp: TimestampFormat, show_thread_name: bool) -> String {
+        let since_epoch = self
+            .time
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_else(|_| Duration::from_secs(0));
+        let time = Local.timestamp(since_epoch.as_secs() as i64, since_epoch.subsec_nanos());
+        let thread = if show_thread_name {
+            format!(
+                " {}",
+                self.thread.as_ref().map_or("<unnamed>", String::as_str)
+            )
+        } else {
+            String::new()
+        };
+        format!(
+            "{} {}{} [{} {}:{}] {}\n",
+            self.level,
+            timestamp.format(time),
+            thread,
+            self.module,
+            pattern::file_name(&self.file),
+            self.line,
+            self.message
+        )
+    }
+
+    /// Serialises the record in the form written by the `BinaryEncoder`.
+    pub(crate) fn serialise(&self) -> Result<Vec<u8>, SerialisationError> {
+        let since_epoch = self
+            .time
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_else(|_| Duration::from_secs(0));
+        serialisation::serialise(&WireRecord {
+            level: self.level as u8,
+            secs: since_epoch.as_secs(),
+            nanos: since_epoch.subsec_nanos(),
+            module: Cow::Borrowed(&self.module),
+            file: Cow::Borrowed(&self.file),
+            line: self.line,
+            thread: self
+                .thread
+                .as_ref()
+                .map(|thread| Cow::Borrowed(&thread[..])),
+            message: Cow::Borrowed(&self.message),
+        })
+    }
+
+    fn from_wire(wire_record: WireRecord) -> Result<Self, BinaryLogError> {
+        let level = match wire_record.level {
+            1 => LogLevel::Error,
+            2 => LogLevel::Warn,
+            3 => LogLevel::Info,
+            4 => LogLevel::Debug,
+            5 => LogLevel::Trace,
+            level => return Err(BinaryLogError::InvalidLevel(level)),
+        };
+        Ok(BinaryRecord {
+            level,
+            time: UNIX_EPOCH + Duration::new(wire_record.secs, wire_record.nanos),
+            module: wire_record.module.into_owned(),
+            file: wire_record.file.into_owned(),
+            line: wire_record.line,
+            thread: wire_record.thread.map(Cow::into_owned),
+            message: wire_record.message.into_owned(),
+        })
+    }
+}
+
+/// Iterator over the records of a binary log.  Iteration stops after the first error.
+pub struct BinaryRecords<R> {
+    reader: BufReader<R>,
+    failed: bool,
+}
+
+impl<R: Read> BinaryRecords<R> {
+    /// Reads records from `reader`, e.g. a `File` written by an appender using the
+    /// [`BinaryEncoder`](struct.BinaryEncoder.html).
+    pub fn new(reader: R) -> Self {
+        BinaryRecords {
+            reader: BufReader::new(reader),
+            failed: false,
+        }
+    }
+
+    fn read_record(&mut self) -> Result<Option<BinaryRecord>, BinaryLogError> {
+        if self.reader.fill_buf()?.is_empty() {
+            return Ok(None);
+        }
+        let wire_record: WireRecord =
+            serialisation::deserialise_from_with_limit(&mut self.reader, Bounded(MAX_RECORD_SIZE))?;
+        BinaryRecord::from_wire(wire_record).map(Some)
+    }
+}
+
+impl<R: Read> Iterator for BinaryRecords<R> {
+    type Item = Result<BinaryRecord, BinaryLogError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        match self.read_record() {
+            Ok(record) => record.map(Ok),
+            Err(error) => {
+                self.failed = true;
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::log::timestamp::TimestampPrecision;
+    use crate::serialisation::serialise;
+
+    fn wire_record(level: u8, message: &str) -> WireRecord {
+        WireRecord {
+            level,
+            secs: 1_500_000_000,
+            nanos: 123_456_789,
+            module: Cow::Borrowed("example::my_mod"),
+            file: Cow::Borrowed("src/my_mod.rs"),
+            line: 10,
+            thread: Some(Cow::Borrowed("Worker")),
+            message: Cow::Borrowed(message),
+        }
+    }
+
+    #[test]
+    fn read_records() {
+        let mut log = unwrap!(serialise(&wire_record(2, "A warning")));
+        log.extend(unwrap!(serialise(&wire_record(5, "A trace"))));
+
+        let records: Vec<_> = BinaryRecords::new(&log[..]).map(|r| unwrap!(r)).collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].level, LogLevel::Warn);
+        assert_eq!(records[0].message, "A warning");
+        assert_eq!(records[1].level, LogLevel::Trace);
+        assert_eq!(records[1].thread, Some("Worker".to_owned()));
+        assert_eq!(
+            records[1].time,
+            UNIX_EPOCH + Duration::new(1_500_000_000, 123_456_789)
+        );
+
+        let time = Local.timestamp(1_500_000_000, 123_456_789);
+        assert_eq!(
+            records[0].to_text(TimestampFormat::new(), true),
+            format!(
+                "WARN {} Worker [example::my_mod my_mod.rs:10] A warning\n",
+                time.format("%H:%M:%S.%f")
+            )
+        );
+        assert_eq!(
+            records[0].to_text(TimestampFormat::new(), false),
+            format!(
+                "WARN {} [example::my_mod my_mod.rs:10] A warning\n",
+                time.format("%H:%M:%S.%f")
+            )
+        );
+        assert_eq!(
+            records[0].to_text(
+                TimestampFormat::new()
+                    .date(true)
+                    .utc(true)
+                    .precision(TimestampPrecision::Millis),
+                false
+            ),
+            "WARN 2017-07-14T02:40:00.123Z [example::my_mod my_mod.rs:10] A warning\n"
+        );
+    }
+
+    #[test]
+    fn read_corrupt_records() {
+        let mut log = unwrap!(serialise(&wire_record(1, "An error")));
+        let truncated = log.len() - 1;
+        let mut records = BinaryRecords::new(&log[..truncated]);
+        match records.next() {
+            Some(Err(BinaryLogError::Serialisation(_))) => (),
+            result => panic!("{:?}", result),
+        }
+        assert!(records.next().is_none());
+
+        log[0] = 9;
+        match BinaryRecords::new(&log[..]).next() {
+            Some(Err(BinaryLogError::InvalidLevel(9))) => (),
+            result => panic!("{:?}", result),
+        }
+    }
+}
@@ -0,0 +1,123 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use log::{LogLevel, LogRecord};
+use log4rs::encode::writer::ansi::AnsiWriter;
+use log4rs::encode::{self, Color, Encode, Style};
+use std::env;
+use std::error::Error;
+use std::str::FromStr;
+
+/// Environment variable which, when set to a non-empty value, disables automatic colouring.
+/// See https://no-color.org.
+const NO_COLOR_ENV_VAR: &str = "NO_COLOR";
+
+/// Whether console output should be coloured according to the level of each record.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColourChoice {
+    /// Colour only if the output is a terminal and `NO_COLOR` is not set.
+    Auto,
+    /// Always colour, even when redirected or if `NO_COLOR` is set.
+    Always,
+    /// Never colour.
+    Never,
+}
+
+impl ColourChoice {
+    /// Resolves the choice for an output which may or may not be a terminal.
+    pub fn enabled(self, is_tty: bool) -> bool {
+        self.resolve(is_tty, no_color_requested())
+    }
+
+    fn resolve(self, is_tty: bool, no_color: bool) -> bool {
+        match self {
+            ColourChoice::Auto => is_tty && !no_color,
+            ColourChoice::Always => true,
+            ColourChoice::Never => false,
+        }
+    }
+}
+
+impl FromStr for ColourChoice {
+    type Err = ();
+
+    fn from_str(choice: &str) -> Result<Self, ()> {
+        match &choice.to_lowercase()[..] {
+            "auto" => Ok(ColourChoice::Auto),
+            "always" => Ok(ColourChoice::Always),
+            "never" => Ok(ColourChoice::Never),
+            _ => Err(()),
+        }
+    }
+}
+
+fn no_color_requested() -> bool {
+    env::var_os(NO_COLOR_ENV_VAR).map_or(false, |value| !value.is_empty())
+}
+
+/// Wraps another encoder, emitting ANSI escape codes to colour each record by its level.
+///
+/// Any styles requested by the inner encoder (e.g. the `{h(..)}` pattern) are emitted too.
+#[derive(Debug)]
+pub struct ColourEncoder(pub Box<Encode>);
+
+impl Encode for ColourEncoder {
+    fn encode(
+        &self,
+        w: &mut encode::Write,
+        record: &LogRecord,
+    ) -> Result<(), Box<Error + Sync + Send>> {
+        let mut w = AnsiWriter(w);
+        encode::Write::set_style(&mut w, &level_style(record.level()))?;
+        self.0.encode(&mut w, record)?;
+        encode::Write::set_style(&mut w, &Style::new())?;
+        Ok(())
+    }
+}
+
+fn level_style(level: LogLevel) -> Style {
+    let mut style = Style::new();
+    let _ = match level {
+        LogLevel::Error => style.text(Color::Red).intense(true),
+        LogLevel::Warn => style.text(Color::Yellow),
+        LogLevel::Info => style.text(Color::Green),
+        LogLevel::Debug => style.text(Color::Cyan),
+        LogLevel::Trace => &mut style,
+    };
+    style
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ColourChoice;
+
+    #[test]
+    fn parse_colour_choice() {
+        assert_eq!(unwrap!("auto".parse::<ColourChoice>()), ColourChoice::Auto);
+        assert_eq!(
+            unwrap!("Always".parse::<ColourChoice>()),
+            ColourChoice::Always
+        );
+        assert_eq!(
+            unwrap!("NEVER".parse::<ColourChoice>()),
+            ColourChoice::Never
+        );
+        assert!("sometimes".parse::<ColourChoice>().is_err());
+    }
+
+    #[test]
+    fn resolve_colour_choice() {
+        assert!(ColourChoice::Auto.resolve(true, false));
+        assert!(!ColourChoice::Auto.resolve(false, false));
+        assert!(!ColourChoice::Auto.resolve(true, true));
+
+        assert!(ColourChoice::Always.resolve(false, true));
+        assert!(!ColourChoice::Never.resolve(true, false));
+    }
+}
@@ -0,0 +1,130 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use log_mdc;
+use std::fmt::Display;
+use std::io::{self, Write};
+
+/// Runs `f` with `key` set to `value` in the logging context of the current thread, restoring the
+/// previous value of `key` afterwards, even if `f` panics.
+///
+/// The context is attached to every record logged on the thread.  Patterns render single values
+/// via `{X(key)}` and the whole context via `{ctx}`, while the JSON encoder writes it as `mdc`.
+pub fn with_context<K, V, F, R>(key: K, value: V, f: F) -> R
+where
+    K: Into<String>,
+    V: Display,
+    F: FnOnce() -> R,
+{
+    let _guard = log_mdc::insert_scoped(key, value.to_string());
+    f()
+}
+
+/// A snapshot of the logging context of one thread, for carrying it over to another.
+///
+/// ```
+/// # extern crate maidsafe_utilities;
+/// use maidsafe_utilities::log::{self, Context};
+/// use maidsafe_utilities::thread;
+///
+/// # fn main() {
+/// log::with_context("req_id", 42, || {
+///     let context = Context::capture();
+///     let _worker = thread::named("Worker", move || {
+///         context.enter(|| {
+///             // Records logged here carry `req_id=42`.
+///         })
+///     });
+/// });
+/// # }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Context(Vec<(String, String)>);
+
+impl Context {
+    /// Captures the logging context of the current thread.
+    pub fn capture() -> Self {
+        let mut entries = Vec::new();
+        log_mdc::iter(|key, value| entries.push((key.to_owned(), value.to_owned())));
+        entries.sort();
+        Context(entries)
+    }
+
+    /// Runs `f` with the captured values added to the logging context of the current thread,
+    /// restoring the previous context afterwards, even if `f` panics.
+    pub fn enter<F: FnOnce() -> R, R>(&self, f: F) -> R {
+        let _guard = log_mdc::extend_scoped(self.0.iter().cloned());
+        f()
+    }
+
+    /// Returns the captured key-value pairs, sorted by key.
+    pub fn entries(&self) -> &[(String, String)] {
+        &self.0
+    }
+}
+
+/// Writes the logging context of the current thread as space-separated `key=value` pairs, sorted
+/// by key.
+pub(crate) fn write_context<W: Write + ?Sized>(w: &mut W) -> io::Result<()> {
+    for (index, &(ref key, ref value)) in Context::capture().entries().iter().enumerate() {
+        if index > 0 {
+            w.write_all(b" ")?;
+        }
+        write!(w, "{}={}", key, value)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::thread;
+    use std::sync::mpsc;
+
+    fn rendered() -> String {
+        let mut buf = Vec::new();
+        unwrap!(write_context(&mut buf));
+        unwrap!(String::from_utf8(buf))
+    }
+
+    #[test]
+    fn scoped_values() {
+        assert_eq!(rendered(), "");
+        with_context("req_id", 1, || {
+            with_context("peer", "abc", || {
+                assert_eq!(rendered(), "peer=abc req_id=1");
+                with_context("req_id", 2, || assert_eq!(rendered(), "peer=abc req_id=2"));
+                assert_eq!(rendered(), "peer=abc req_id=1");
+            });
+            assert_eq!(rendered(), "req_id=1");
+        });
+        assert_eq!(rendered(), "");
+        assert_eq!(Context::capture(), Context::default());
+    }
+
+    #[test]
+    fn carried_across_threads() {
+        let (tx, rx) = mpsc::channel();
+        let context = with_context("req_id", 7, Context::capture);
+        assert_eq!(
+            context.entries(),
+            &[("req_id".to_owned(), "7".to_owned())][..]
+        );
+
+        let _worker = thread::named("Worker", move || {
+            let before = rendered();
+            let inside = context.enter(rendered);
+            unwrap!(tx.send((before, inside, rendered())));
+        });
+        assert_eq!(
+            unwrap!(rx.recv()),
+            (String::new(), "req_id=7".to_owned(), String::new())
+        );
+    }
+}
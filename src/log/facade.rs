@@ -0,0 +1,39 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+//! Records logged via the `log` 0.4 facade reach the log4rs logger through `log` 0.3.9, which
+//! registers itself as the 0.4 logger when log4rs is initialised.  It forwards the level, target,
+//! line and message, but replaces the module path and file with a placeholder and drops any
+//! key-value data.  Since the target of a 0.4 record defaults to its module path, the module path
+//! is recovered from the target here, which is wrong for records given an explicit target.
+//!
+//! A logger of our own can't take the place of the 0.3.9 one: log4rs 0.7 appenders only accept
+//! `log` 0.3 `LogRecord`s, which only the `log` crate can construct, and only while it owns the
+//! 0.4 logger.  Forwarding the file and key-value data needs a log4rs built on `log` 0.4.
+
+use log::LogRecord;
+
+/// Module path and file given to records forwarded from the `log` 0.4 facade.
+const FORWARDED_LOCATION: &str = "<unknown>";
+
+/// Returns whether `record` was forwarded from the `log` 0.4 facade.
+pub(crate) fn is_forwarded(record: &LogRecord) -> bool {
+    record.location().module_path() == FORWARDED_LOCATION
+        && record.location().file() == FORWARDED_LOCATION
+}
+
+/// Returns the module path of `record`, or its target if it was forwarded from the `log` 0.4
+/// facade.
+pub(crate) fn module_path<'a>(record: &'a LogRecord) -> &'a str {
+    if is_forwarded(record) {
+        record.target()
+    } else {
+        record.location().module_path()
+    }
+}
@@ -0,0 +1,144 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use chrono::Local;
+use hostname;
+use std::env;
+use std::fmt::Write;
+use std::process;
+
+/// Date format used by `{date}` when none is given.
+const DEFAULT_DATE_FORMAT: &str = "%Y%m%d";
+
+/// Expands the placeholders in a log file name template:
+///
+/// * `{pid}` - the ID of the current process
+/// * `{hostname}` - the name of the host machine
+/// * `{date}` or `{date:<format>}` - the current local date, formatted with the given `strftime`
+///   style format (`%Y%m%d` by default)
+/// * `{exe}` - the file stem of the current executable
+/// * `{session_id}` - the given `session_id`, which is then required
+///
+/// Literal braces can be written as `{{` and `}}`.
+pub fn expand(template: &str, session_id: Option<&str>) -> Result<String, String> {
+    let mut expanded = String::with_capacity(template.len());
+    let mut chars = template.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                let _ = chars.next();
+                expanded.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                let _ = chars.next();
+                expanded.push('}');
+            }
+            '{' => {
+                let mut placeholder = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => placeholder.push(c),
+                        None => {
+                            return Err(format!(
+                                "Invalid log file name template `{}`: unmatched `{{`",
+                                template
+                            ));
+                        }
+                    }
+                }
+                let mut parts = placeholder.splitn(2, ':');
+                let name = parts.next().unwrap_or("");
+                expand_placeholder(name, parts.next(), session_id, &mut expanded)
+                    .map_err(|e| format!("Invalid log file name template `{}`: {}", template, e))?;
+            }
+            '}' => {
+                return Err(format!(
+                    "Invalid log file name template `{}`: unmatched `}}`",
+                    template
+                ));
+            }
+            _ => expanded.push(c),
+        }
+    }
+
+    Ok(expanded)
+}
+
+fn expand_placeholder(
+    name: &str,
+    arg: Option<&str>,
+    session_id: Option<&str>,
+    expanded: &mut String,
+) -> Result<(), String> {
+    match (name, arg) {
+        ("pid", None) => expanded.push_str(&process::id().to_string()),
+        ("hostname", None) => {
+            let hostname =
+                hostname::get_hostname().ok_or_else(|| "could not get hostname".to_owned())?;
+            expanded.push_str(&hostname);
+        }
+        ("date", format) => {
+            let format = format.unwrap_or(DEFAULT_DATE_FORMAT);
+            write!(expanded, "{}", Local::now().format(format))
+                .map_err(|_| format!("invalid date format `{}`", format))?;
+        }
+        ("exe", None) => {
+            let exe = env::current_exe().map_err(|e| format!("could not get executable: {}", e))?;
+            let stem = exe
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .ok_or_else(|| "could not get executable name".to_owned())?;
+            expanded.push_str(stem);
+        }
+        ("session_id", None) => {
+            let session_id = session_id
+                .ok_or_else(|| "`{session_id}` used but no `session_id` given".to_owned())?;
+            expanded.push_str(session_id);
+        }
+        _ => return Err(format!("unknown placeholder `{{{}}}`", name)),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::expand;
+    use chrono::Local;
+    use std::process;
+
+    #[test]
+    fn expand_placeholders() {
+        assert_eq!(unwrap!(expand("node.log", None)), "node.log");
+        assert_eq!(
+            unwrap!(expand("node-{pid}.log", None)),
+            format!("node-{}.log", process::id())
+        );
+        assert_eq!(
+            unwrap!(expand("{session_id}-{{literal}}.log", Some("abc"))),
+            "abc-{literal}.log"
+        );
+        assert_eq!(
+            unwrap!(expand("{date:%Y}.log", None)),
+            format!("{}.log", Local::now().format("%Y"))
+        );
+        assert!(!unwrap!(expand("{hostname}", None)).is_empty());
+        assert!(!unwrap!(expand("{exe}", None)).is_empty());
+    }
+
+    #[test]
+    fn reject_invalid_templates() {
+        assert!(expand("{session_id}.log", None).is_err());
+        assert!(expand("{uid}.log", None).is_err());
+        assert!(expand("{pid:x}.log", None).is_err());
+        assert!(expand("node}.log", None).is_err());
+        assert!(expand("node-{pid", None).is_err());
+    }
+}
@@ -0,0 +1,144 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use crate::log::context::Context;
+use crate::log::facade;
+use chrono::{DateTime, Local};
+use log::{LogLevel, LogRecord};
+use log4rs::encode::{self, Encode};
+use serde::ser::{Serialize, SerializeMap, Serializer};
+use serde_json;
+use std::error::Error;
+use std::fmt::Display;
+use std::io::Write;
+use std::thread;
+
+/// An encoder writing each record as one line of JSON, with the same fields as the log4rs
+/// `JsonEncoder`: `time` (RFC 3339), `message`, `module_path`, `file`, `line`, `level`, `target`,
+/// `thread` and `mdc`, which holds the context set via [`with_context`](fn.with_context.html).
+///
+/// Unlike the log4rs encoder, records forwarded from the `log` 0.4 facade show their target as
+/// `module_path` rather than `<unknown>`.
+#[derive(Debug, Default)]
+pub struct JsonEncoder;
+
+impl JsonEncoder {
+    /// Creates a new JSON encoder.
+    pub fn new() -> Self {
+        JsonEncoder
+    }
+}
+
+impl Encode for JsonEncoder {
+    fn encode(
+        &self,
+        w: &mut encode::Write,
+        record: &LogRecord,
+    ) -> Result<(), Box<Error + Sync + Send>> {
+        let thread = thread::current();
+        JsonRecord {
+            time: Local::now(),
+            message: record.args(),
+            module_path: facade::module_path(record),
+            file: record.location().file(),
+            line: record.location().line(),
+            level: record.level(),
+            target: record.target(),
+            thread: thread.name(),
+        }
+        .write(w)
+    }
+}
+
+/// The fields written by the `JsonEncoder`, apart from the context of the current thread, which is
+/// added when the record is written.
+pub(crate) struct JsonRecord<'a, M: Display> {
+    pub time: DateTime<Local>,
+    pub message: M,
+    pub module_path: &'a str,
+    pub file: &'a str,
+    pub line: u32,
+    pub level: LogLevel,
+    pub target: &'a str,
+    pub thread: Option<&'a str>,
+}
+
+impl<'a, M: Display> JsonRecord<'a, M> {
+    /// Writes the record followed by a newline.
+    pub fn write<W: Write + ?Sized>(&self, w: &mut W) -> Result<(), Box<Error + Sync + Send>> {
+        serde_json::to_writer(&mut *w, self)?;
+        w.write_all(b"\n")?;
+        Ok(())
+    }
+}
+
+impl<'a, M: Display> Serialize for JsonRecord<'a, M> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(9))?;
+        map.serialize_entry("time", &self.time.to_rfc3339())?;
+        map.serialize_entry("message", &self.message.to_string())?;
+        map.serialize_entry("module_path", self.module_path)?;
+        map.serialize_entry("file", self.file)?;
+        map.serialize_entry("line", &self.line)?;
+        map.serialize_entry("level", &self.level.to_string())?;
+        map.serialize_entry("target", self.target)?;
+        map.serialize_entry("thread", &self.thread)?;
+        map.serialize_entry("mdc", &ContextMap(Context::capture()))?;
+        map.end()
+    }
+}
+
+struct ContextMap(Context);
+
+impl Serialize for ContextMap {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.0.entries().len()))?;
+        for &(ref key, ref value) in self.0.entries() {
+            map.serialize_entry(key, value)?;
+        }
+        map.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::log::context;
+    use serde_json::Value;
+
+    #[test]
+    fn fields() {
+        let mut buf = Vec::new();
+        context::with_context("req_id", 7, || {
+            unwrap!(JsonRecord {
+                time: Local::now(),
+                message: format_args!("Hello {}", "world"),
+                module_path: "routing::core",
+                file: "src/core.rs",
+                line: 42,
+                level: LogLevel::Warn,
+                target: "routing::core",
+                thread: Some("Worker"),
+            }
+            .write(&mut buf))
+        });
+
+        assert_eq!(buf.last(), Some(&b'\n'));
+        let json: Value = unwrap!(serde_json::from_slice(&buf));
+        assert!(DateTime::parse_from_rfc3339(unwrap!(json["time"].as_str())).is_ok());
+        assert_eq!(json["message"], "Hello world");
+        assert_eq!(json["module_path"], "routing::core");
+        assert_eq!(json["file"], "src/core.rs");
+        assert_eq!(json["line"], 42);
+        assert_eq!(json["level"], "WARN");
+        assert_eq!(json["target"], "routing::core");
+        assert_eq!(json["thread"], "Worker");
+        assert_eq!(json["mdc"]["req_id"], "7");
+    }
+}
@@ -0,0 +1,228 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+//! Merging of several logs into one chronologically ordered stream.
+//!
+//! The default pattern only records the time of day, so each log is assumed to start on the same
+//! day, and a log is taken to have passed midnight whenever its timestamps jump backwards by more
+//! than twelve hours.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! # #[macro_use]
+//! # extern crate unwrap;
+//! # extern crate maidsafe_utilities;
+//! use maidsafe_utilities::log::merge::Merge;
+//! use std::fs::File;
+//! use std::io::BufReader;
+//!
+//! # fn main() {
+//! let mut merge = Merge::new();
+//! for name in &["node-1.log", "node-2.log"] {
+//!     merge.add(*name, BufReader::new(unwrap!(File::open(name))));
+//! }
+//! for entry in merge {
+//!     print!("{}", unwrap!(entry));
+//! }
+//! # }
+//! ```
+
+use super::reader::{LogEntry, LogReader, LogTime};
+use chrono::{Duration, NaiveTime};
+use std::cmp::Ordering;
+use std::fmt::{self, Display, Formatter};
+use std::io::{self, BufRead};
+
+/// A backwards jump in the time of day larger than this is taken to be a rollover past midnight.
+const ROLLOVER_THRESHOLD_HOURS: i64 = 12;
+
+/// An entry of a merged log, tagged with the name of the log it was read from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MergedEntry {
+    /// Name of the log this entry was read from.
+    pub source: String,
+    /// The entry itself.
+    pub entry: LogEntry,
+}
+
+impl Display for MergedEntry {
+    /// Formats the entry in the default text format, prefixed by its source in square brackets.
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(formatter, "[{}] {}", self.source, self.entry)
+    }
+}
+
+/// Iterator merging the entries of several logs in chronological order.  Entries with equal
+/// timestamps are yielded in the order in which their logs were added.
+pub struct Merge<R> {
+    sources: Vec<Source<R>>,
+}
+
+struct Source<R> {
+    name: String,
+    reader: LogReader<R>,
+    head: Option<(SortKey, LogEntry)>,
+    day: u32,
+    last_time: Option<NaiveTime>,
+}
+
+#[derive(Clone, Copy)]
+struct SortKey {
+    day: u32,
+    time: LogTime,
+}
+
+impl SortKey {
+    fn compare(&self, other: &SortKey) -> Ordering {
+        match (self.time, other.time) {
+            (LogTime::DateTime(lhs), LogTime::DateTime(rhs)) => lhs.cmp(&rhs),
+            _ => (self.day, self.time.time_of_day()).cmp(&(other.day, other.time.time_of_day())),
+        }
+    }
+}
+
+impl<R: BufRead> Source<R> {
+    /// Ensures `head` holds the next entry, if there is one.
+    fn fill(&mut self) -> io::Result<()> {
+        if self.head.is_some() {
+            return Ok(());
+        }
+        let entry = match self.reader.next() {
+            Some(entry) => entry?,
+            None => return Ok(()),
+        };
+        let time = entry.time.time_of_day();
+        if let Some(last_time) = self.last_time {
+            if last_time.signed_duration_since(time) > Duration::hours(ROLLOVER_THRESHOLD_HOURS) {
+                self.day += 1;
+            }
+        }
+        self.last_time = Some(time);
+        let key = SortKey {
+            day: self.day,
+            time: entry.time,
+        };
+        self.head = Some((key, entry));
+        Ok(())
+    }
+}
+
+impl<R: BufRead> Merge<R> {
+    /// Creates an empty merge.
+    pub fn new() -> Self {
+        Merge {
+            sources: Vec::new(),
+        }
+    }
+
+    /// Adds the log read from `reader`, tagging its entries with `name`.
+    pub fn add<S: Into<String>>(&mut self, name: S, reader: R) {
+        self.add_reader(name, LogReader::new(reader))
+    }
+
+    /// Adds the log read by `reader`, e.g. one with a filter applied, tagging its entries with
+    /// `name`.
+    pub fn add_reader<S: Into<String>>(&mut self, name: S, reader: LogReader<R>) {
+        self.sources.push(Source {
+            name: name.into(),
+            reader,
+            head: None,
+            day: 0,
+            last_time: None,
+        });
+    }
+}
+
+impl<R: BufRead> Default for Merge<R> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<R: BufRead> Iterator for Merge<R> {
+    type Item = io::Result<MergedEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut earliest: Option<(usize, SortKey)> = None;
+        for (index, source) in self.sources.iter_mut().enumerate() {
+            if let Err(error) = source.fill() {
+                return Some(Err(error));
+            }
+            if let Some((key, _)) = source.head {
+                let is_earlier = match earliest {
+                    Some((_, earliest_key)) => key.compare(&earliest_key) == Ordering::Less,
+                    None => true,
+                };
+                if is_earlier {
+                    earliest = Some((index, key));
+                }
+            }
+        }
+
+        let source = &mut self.sources[earliest?.0];
+        let (_, entry) = source.head.take()?;
+        Some(Ok(MergedEntry {
+            source: source.name.clone(),
+            entry,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NODE_1: &str = "\
+INFO 23:59:58.000000000 [node main.rs:1] one-a
+INFO 23:59:59.500000000 [node main.rs:1] one-b
+continued
+INFO 00:00:01.000000000 [node main.rs:1] one-c
+";
+
+    const NODE_2: &str = "\
+INFO 23:59:59.000000000 [node main.rs:1] two-a
+INFO 00:00:00.500000000 [node main.rs:1] two-b
+INFO 00:00:01.000000000 [node main.rs:1] two-c
+";
+
+    #[test]
+    fn merge_across_midnight() {
+        let mut merge = Merge::new();
+        merge.add("node-1", NODE_1.as_bytes());
+        merge.add("node-2", NODE_2.as_bytes());
+        let merged: Vec<_> = merge.map(|entry| unwrap!(entry)).collect();
+
+        let messages: Vec<_> = merged.iter().map(|m| &m.entry.message[..]).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "one-a",
+                "two-a",
+                "one-b\ncontinued",
+                "two-b",
+                "one-c",
+                "two-c"
+            ]
+        );
+        assert_eq!(merged[1].source, "node-2");
+        assert_eq!(
+            merged[0].to_string(),
+            "[node-1] INFO 23:59:58.000000000 [node main.rs:1] one-a\n"
+        );
+    }
+
+    #[test]
+    fn merge_empty() {
+        let mut merge = Merge::new();
+        assert!(merge.next().is_none());
+        merge.add("empty", &b""[..]);
+        assert!(merge.next().is_none());
+    }
+}
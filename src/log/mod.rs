@@ -11,7 +11,67 @@
 //! more fine-grained control, create a file called `log.toml` in the root directory of the project,
 //! or in the same directory where the executable is.  See
 //! [log4rs docs](http://sfackler.github.io/log4rs/doc/v0.3.3/log4rs/index.html) for details about
-//! the format and structure of this file.
+//! the format and structure of this file.  Patterns given in `log.toml` for the async appenders
+//! additionally accept `{fn}`, which outputs the file name of the log statement without its
+//! directory (see [`PatternEncoder`](struct.PatternEncoder.html)).  The `output_file_name` of an
+//! `async_file` appender may contain the placeholders `{pid}`, `{hostname}`, `{exe}`, `{date}` (or
+//! `{date:<strftime format>}`) and `{session_id}` (taken from the appender's `session_id` key), so
+//! that several processes sharing a configuration write to distinct files.  A relative
+//! `output_file_name` is resolved against the directory given by the `MAIDSAFE_LOG_DIR`
+//! environment variable if set, else the appender's `log_dir` key, else the default directory
+//! chosen by `config_file_handler`.  An absolute one is used as-is unless either of the former is
+//! set, in which case only its file name is kept.  Missing directories are created.
+//!
+//! Every async appender accepts `encoding = "json"` in place of a `pattern`, writing one JSON
+//! object per line for ingestion by log shippers, or `encoding = "binary"` for compact records
+//! which can be turned back into text by the `maidsafe_log_decode` binary or read via
+//! [`BinaryRecords`](struct.BinaryRecords.html).
+//!
+//! Text and JSON lines logs can be parsed and filtered with the [`reader`](reader/index.html)
+//! module, or from the command line with the `maidsafe_log_query` binary.  Logs of several nodes
+//! can be interleaved chronologically with the [`merge`](merge/index.html) module or the
+//! `maidsafe_log_merge` binary.
+//!
+//! Timestamps default to the local time of day.
+//! [`set_timestamp_format`](fn.set_timestamp_format.html) selects full ISO-8601 dates, UTC and the
+//! precision for the `init*` functions.  Patterns in
+//! `log.toml` can use `{ts}` for a timestamp configured by the appender's `timestamp` (`"time"` or
+//! `"iso8601"`), `timestamp_zone` (`"local"` or `"utc"`) and `timestamp_precision` (`"s"`, `"ms"`,
+//! `"us"` or `"ns"`) keys, with the same defaults.  Without a `pattern`, these keys apply to the
+//! log4rs default pattern with its date replaced by `{ts}`.  JSON and binary encodings reject them.
+//!
+//! Each async appender can suppress repeated or excessive records, see
+//! [`RateLimit`](struct.RateLimit.html).  In `log.toml`, `dedup_window_ms` drops records repeating
+//! the previous message of their call site within that many milliseconds, and
+//! `rate_limit_per_second` (with an optional `rate_limit_burst`) caps the records of each module.
+//! High-volume levels can be thinned out before encoding with [`Sampling`](struct.Sampling.html),
+//! given in `log.toml` as a `sampling` array of tables, each holding a `level`, an optional
+//! `module` and either `one_in` or `probability`.
+//!
+//! Sensitive values such as keys and file paths can be masked in the output of all async
+//! appenders via [`set_redaction`](fn.set_redaction.html).  An appender can opt out, e.g. for
+//! local debugging, with `redact = false`.
+//!
+//! [`install_panic_hook`](fn.install_panic_hook.html) logs panics, including those in threads
+//! spawned via `thread::named`, and makes sure queued records are written before the panic
+//! unwinds further.  Queued records can also be written out at any time via
+//! [`flush`](fn.flush.html).
+//!
+//! Values set via [`with_context`](fn.with_context.html), e.g. a request ID, are attached to every
+//! record logged on the current thread while they are in scope, and can be carried over to another
+//! thread via [`Context`](struct.Context.html).  Patterns render them via `{ctx}` or `{X(key)}`,
+//! and the JSON encoding writes them as an `mdc` object.
+//!
+//! Records from crates using version 0.4 of the `log` facade are forwarded to the same appenders
+//! and governed by the same configuration, with loggers in `log.toml` matching their targets.  The
+//! forwarding drops their file and any key-value data, but `{M}` in patterns, sampling and rate
+//! limits use their target as the module path.  Records logged with an explicit `target:`
+//! therefore show that target as their module.
+//!
+//! Every async appender counts the records it has queued, written, failed to write and dropped,
+//! along with the bytes written and, for web socket appenders, reconnection attempts.
+//! [`stats`](fn.stats.html) returns a snapshot of these counters, and
+//! [`report_stats`](fn.report_stats.html) logs them periodically.
 //!
 //! An example of a log message is:
 //!
@@ -31,6 +91,11 @@
 //! enabled, and the thread executing the log statement is unnamed, the thread name is shown as
 //! `<unnamed>`.
 //!
+//! Console output is coloured according to the level of each message when stdout is a terminal.
+//! Set the `NO_COLOR` environment variable to disable this, or use the `color` key of an
+//! `async_console` appender in `log.toml` (`"auto"`, `"always"` or `"never"`).  JSON and binary
+//! encodings are never coloured.
+//!
 //! The functions can safely be called multiple times concurrently.
 //!
 //! #Examples
@@ -73,10 +138,38 @@
 //! `Trace` and more severe. Thus `mod0` will log at `Error` level and `mod1` at `Trace` and more
 //! severe ones.
 
-pub use self::async_log::MSG_TERMINATOR;
+pub use self::async_log::{
+    flush, AsyncAppender, AsyncAppenderBuilder, AsyncAppenderCreator, AsyncWriterAppender,
+    ConsoleTarget, Encoding, SyncWrite, WriteSink, LOG_DIR_ENV_VAR, MSG_TERMINATOR,
+};
+pub use self::binary::{BinaryEncoder, BinaryLogError, BinaryRecord, BinaryRecords};
+pub use self::context::{with_context, Context};
+pub use self::json::JsonEncoder;
+pub use self::panic_hook::install_panic_hook;
+pub use self::pattern::PatternEncoder;
+pub use self::rate_limit::RateLimit;
+pub use self::redaction::{set_redaction, Redaction};
+pub use self::sampling::{SampleRate, Sampling};
+pub use self::stats::{report_stats, stats, AppenderStats, StatsReporter};
+pub use self::timestamp::{TimestampFormat, TimestampPrecision};
 pub use self::web_socket::validate_request as validate_web_socket_request;
 
 mod async_log;
+mod binary;
+mod colour;
+mod context;
+mod facade;
+mod file_name;
+mod json;
+pub mod merge;
+mod panic_hook;
+mod pattern;
+mod rate_limit;
+pub mod reader;
+mod redaction;
+mod sampling;
+mod stats;
+mod timestamp;
 mod web_socket;
 
 use self::async_log::{
@@ -89,29 +182,56 @@ use config_file_handler::FileHandler;
 use log::LogLevelFilter;
 use log4rs;
 use log4rs::config::{Appender, Config, Logger, Root};
-use log4rs::encode::json::JsonEncoder;
-use log4rs::encode::pattern::PatternEncoder;
+use log4rs::encode::Encode;
 use log4rs::file::Deserializers;
 use std::borrow::Borrow;
 use std::env;
 use std::fmt::{self, Display, Formatter};
 use std::net::ToSocketAddrs;
 use std::path::Path;
-use std::sync::{Once, ONCE_INIT};
+use std::sync::{Mutex, Once, ONCE_INIT};
 
 static INITIALISE_LOGGER: Once = ONCE_INIT;
 static CONFIG_FILE: &str = "log.toml";
 static DEFAULT_LOG_LEVEL_FILTER: LogLevelFilter = LogLevelFilter::Warn;
 
+lazy_static! {
+    static ref TIMESTAMP_FORMAT: Mutex<TimestampFormat> = Mutex::new(TimestampFormat::new());
+}
+
+/// Sets the format of the timestamps written by the `init*` functions of this module.  This has no
+/// effect on appenders configured via `log.toml`, which use the `timestamp*` keys instead.
+///
+/// Must be called before initialising the logger to take effect.
+pub fn set_timestamp_format(format: TimestampFormat) {
+    *unwrap!(TIMESTAMP_FORMAT.lock()) = format;
+}
+
 /// Initialises the `env_logger` for output to stdout.
 ///
 /// For further details, see the [module docs](index.html).
 pub fn init(show_thread_name: bool) -> Result<(), String> {
-    init_once_guard(|| init_impl(show_thread_name, None))
+    init_once_guard(|| init_impl(show_thread_name, None, Deserializers::default()))
+}
+
+/// Initialises the `env_logger` for output to stdout, making the given `deserializers` available
+/// to `log.toml` in addition to the built-in async appenders.
+///
+/// This allows custom appender kinds to be configured from the log file, e.g. an
+/// [`AsyncAppenderCreator`](struct.AsyncAppenderCreator.html) wrapping a user-defined
+/// [`SyncWrite`](trait.SyncWrite.html) sink.
+///
+/// For further details, see the [module docs](index.html).
+pub fn init_with_deserializers(
+    show_thread_name: bool,
+    deserializers: Deserializers,
+) -> Result<(), String> {
+    init_once_guard(|| init_impl(show_thread_name, None, deserializers))
 }
 
 /// Initialises the `env_logger` for output to stdout and takes
-/// an output file name that will override the log configuration.
+/// an output file name that will override the log configuration.  Unlike `output_file_name` in
+/// `log.toml`, the override is used as-is, without expanding placeholders.
 ///
 /// For further details, see the [module docs](index.html).
 pub fn init_with_output_file<S>(
@@ -121,16 +241,25 @@ pub fn init_with_output_file<S>(
 where
     S: Into<String>,
 {
-    init_once_guard(|| init_impl(show_thread_name, Some(output_file_name_override.into())))
+    init_once_guard(|| {
+        init_impl(
+            show_thread_name,
+            Some(output_file_name_override.into()),
+            Deserializers::default(),
+        )
+    })
 }
 
-fn init_impl(show_thread_name: bool, op_file_name_override: Option<String>) -> Result<(), String> {
+fn init_impl(
+    show_thread_name: bool,
+    op_file_name_override: Option<String>,
+    mut deserializers: Deserializers,
+) -> Result<(), String> {
     let log_config_path = FileHandler::<()>::open(CONFIG_FILE, false)
         .ok()
         .and_then(|fh| Some(fh.path().to_path_buf()));
 
     if let Some(config_path) = log_config_path {
-        let mut deserializers = Deserializers::default();
         deserializers.insert("async_console", AsyncConsoleAppenderCreator);
         deserializers.insert(
             "async_file",
@@ -174,6 +303,24 @@ pub fn init_to_file<P: AsRef<Path>>(
     show_thread_name: bool,
     file_path: P,
     log_to_console: bool,
+) -> Result<(), String> {
+    init_to_file_with_encoding(
+        show_thread_name,
+        file_path,
+        log_to_console,
+        Encoding::Pattern,
+    )
+}
+
+/// Initialises the `env_logger` for output to a file and optionally to the console asynchronously,
+/// using the given `encoding` for the file.  Console output, if enabled, is always human-readable.
+///
+/// For further details, see the [module docs](index.html).
+pub fn init_to_file_with_encoding<P: AsRef<Path>>(
+    show_thread_name: bool,
+    file_path: P,
+    log_to_console: bool,
+    encoding: Encoding,
 ) -> Result<(), String> {
     let mut result = Err("Logger already initialised".to_owned());
 
@@ -196,8 +343,14 @@ pub fn init_to_file<P: AsRef<Path>>(
 
         let mut config = Config::builder().loggers(loggers);
 
+        let file_encoder: Box<Encode> = match encoding {
+            Encoding::Pattern => Box::new(make_pattern(show_thread_name)),
+            Encoding::Json => Box::new(JsonEncoder::new()),
+            Encoding::Binary => Box::new(BinaryEncoder::new()),
+        };
         let file_appender = AsyncFileAppender::builder(file_path)
-            .encoder(Box::new(make_pattern(show_thread_name)))
+            .encoder(file_encoder)
+            .encoding(encoding)
             .append(false)
             .build();
         let file_appender = match file_appender {
@@ -322,6 +475,7 @@ pub fn init_to_web_socket<U: Borrow<str>>(
 
         let server_appender = AsyncWebSockAppender::builder(server_url)
             .encoder(Box::new(JsonEncoder::new()))
+            .encoding(Encoding::Json)
             .session_id(session_id)
             .build()
             .map_err(|e| format!("{}", e))?;
@@ -350,12 +504,12 @@ pub fn init_to_web_socket<U: Borrow<str>>(
 
 fn make_pattern(show_thread_name: bool) -> PatternEncoder {
     let pattern = if show_thread_name {
-        "{l} {d(%H:%M:%S.%f)} {T} [{M} #FS#{f}#FE#:{L}] {m}{n}"
+        "{l} {ts} {T} [{M} {fn}:{L}] {m}{n}"
     } else {
-        "{l} {d(%H:%M:%S.%f)} [{M} #FS#{f}#FE#:{L}] {m}{n}"
+        "{l} {ts} [{M} {fn}:{L}] {m}{n}"
     };
 
-    PatternEncoder::new(pattern)
+    PatternEncoder::with_timestamp(pattern, *unwrap!(TIMESTAMP_FORMAT.lock()))
 }
 
 #[derive(Debug)]
@@ -0,0 +1,67 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use super::async_log;
+use backtrace::Backtrace;
+use std::any::Any;
+use std::panic::{self, Location};
+use std::thread;
+use std::time::Duration;
+
+/// Upper limit on how long a panicking thread waits for the async appenders to drain.
+const FLUSH_TIMEOUT_SECS: u64 = 5;
+
+/// Installs a panic hook which logs each panic at `Error` level, then waits for the async
+/// appenders to write all queued records before continuing to unwind.  Panics on the background
+/// thread of an async appender don't wait, as that thread can't drain its own queue.
+///
+/// The logged record holds the panic message, its location, the name of the panicking thread and
+/// a backtrace.  The previously installed hook, by default the one printing to stderr, is still
+/// run afterwards.  The logger should be initialised before calling this.
+pub fn install_panic_hook() {
+    let previous_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        log_panic(info.payload(), info.location());
+        if thread::current().name() != Some(async_log::WORKER_THREAD_NAME) {
+            let _ = async_log::flush(Duration::from_secs(FLUSH_TIMEOUT_SECS));
+        }
+        previous_hook(info);
+    }));
+}
+
+fn log_panic(payload: &(Any + Send), location: Option<&Location>) {
+    let message = if let Some(message) = payload.downcast_ref::<&str>() {
+        *message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        &message[..]
+    } else {
+        "Box<Any>"
+    };
+    let location = location.map_or_else(
+        || "<unknown location>".to_owned(),
+        |location| {
+            format!(
+                "{}:{}:{}",
+                location.file(),
+                location.line(),
+                location.column()
+            )
+        },
+    );
+    let thread = thread::current();
+    let thread_name = thread.name().unwrap_or("<unnamed>");
+
+    error!(
+        "Thread '{}' panicked at '{}', {}\nstack backtrace:\n{:?}",
+        thread_name,
+        message,
+        location,
+        Backtrace::new()
+    );
+}
@@ -0,0 +1,218 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use crate::log::context;
+use crate::log::facade;
+use crate::log::timestamp::{TimestampFormat, TIMESTAMP_TOKEN};
+use log::LogRecord;
+use log4rs::encode::pattern::PatternEncoder as Log4rsPatternEncoder;
+use log4rs::encode::{self, Encode};
+use std::error::Error;
+
+/// Token which is replaced by the file name (without its directory) of the log statement.
+const FILE_NAME_TOKEN: &str = "{fn}";
+
+/// Token which is replaced by the logging context of the current thread.
+const CONTEXT_TOKEN: &str = "{ctx}";
+
+/// Tokens which are replaced by the module path of the log statement.
+const MODULE_TOKENS: [&str; 2] = ["{M}", "{module}"];
+
+/// Legacy marker pair which was previously post-processed to strip directories from `{f}`.
+const LEGACY_FILE_NAME: &str = "#FS#{f}#FE#";
+
+/// An encoder accepting the log4rs pattern syntax, extended with a `{fn}` token which outputs just
+/// the file name of the log statement, e.g. `main.rs` rather than `src/bin/main.rs`.
+///
+/// `{ctx}` outputs the logging context set via [`with_context`](fn.with_context.html) as
+/// space-separated `key=value` pairs sorted by key, or nothing if it is empty.
+///
+/// `{M}` and `{module}` output the module path as in log4rs, except for records forwarded from the
+/// `log` 0.4 facade, which lack a module path and show their target instead.
+///
+/// `{ts}` outputs the time of the record in the [`TimestampFormat`](struct.TimestampFormat.html)
+/// given to [`with_timestamp`](#method.with_timestamp), or in the default format.
+///
+/// These tokens are only recognised at the top level of the pattern, i.e. not as an argument to
+/// another formatter such as `{h(..)}`.  For backwards compatibility, `#FS#{f}#FE#` is treated as
+/// `{fn}`.
+#[derive(Debug)]
+pub struct PatternEncoder(Vec<Segment>);
+
+#[derive(Debug)]
+enum Segment {
+    Pattern(Log4rsPatternEncoder),
+    FileName,
+    Module,
+    Context,
+    Timestamp(Log4rsPatternEncoder),
+}
+
+impl PatternEncoder {
+    /// Creates a new encoder from the given pattern.
+    pub fn new(pattern: &str) -> Self {
+        Self::with_timestamp(pattern, TimestampFormat::new())
+    }
+
+    /// Creates a new encoder from the given pattern, writing `{ts}` in the given format.
+    pub fn with_timestamp(pattern: &str, timestamp: TimestampFormat) -> Self {
+        let pattern = pattern.replace(LEGACY_FILE_NAME, FILE_NAME_TOKEN);
+        let segments = split(&pattern)
+            .into_iter()
+            .map(|part| match part {
+                Part::Text(text) => Segment::Pattern(Log4rsPatternEncoder::new(text)),
+                Part::FileName => Segment::FileName,
+                Part::Module => Segment::Module,
+                Part::Context => Segment::Context,
+                Part::Timestamp => {
+                    Segment::Timestamp(Log4rsPatternEncoder::new(&timestamp.pattern()))
+                }
+            })
+            .collect();
+        PatternEncoder(segments)
+    }
+}
+
+impl Encode for PatternEncoder {
+    fn encode(
+        &self,
+        w: &mut encode::Write,
+        record: &LogRecord,
+    ) -> Result<(), Box<Error + Sync + Send>> {
+        for segment in &self.0 {
+            match *segment {
+                Segment::Pattern(ref encoder) | Segment::Timestamp(ref encoder) => {
+                    encoder.encode(w, record)?
+                }
+                Segment::FileName => w.write_all(file_name(record.location().file()).as_bytes())?,
+                Segment::Module => w.write_all(facade::module_path(record).as_bytes())?,
+                Segment::Context => context::write_context(w)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Part<'a> {
+    Text(&'a str),
+    FileName,
+    Module,
+    Context,
+    Timestamp,
+}
+
+/// Splits `pattern` around each top-level `{fn}`, `{ctx}`, `{ts}` and module token, skipping
+/// escaped braces and nested formatters.
+fn split(pattern: &str) -> Vec<Part> {
+    let mut parts = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    let mut index = 0;
+    let bytes = pattern.as_bytes();
+
+    while index < bytes.len() {
+        let rest = &pattern[index..];
+        if rest.starts_with("{{") || rest.starts_with("}}") {
+            index += 2;
+            continue;
+        }
+        match bytes[index] {
+            b'{' if depth == 0 => {
+                let token = if rest.starts_with(FILE_NAME_TOKEN) {
+                    Some((Part::FileName, FILE_NAME_TOKEN.len()))
+                } else if rest.starts_with(CONTEXT_TOKEN) {
+                    Some((Part::Context, CONTEXT_TOKEN.len()))
+                } else if rest.starts_with(TIMESTAMP_TOKEN) {
+                    Some((Part::Timestamp, TIMESTAMP_TOKEN.len()))
+                } else {
+                    MODULE_TOKENS
+                        .iter()
+                        .find(|token| rest.starts_with(*token))
+                        .map(|token| (Part::Module, token.len()))
+                };
+                if let Some((part, len)) = token {
+                    if start < index {
+                        parts.push(Part::Text(&pattern[start..index]));
+                    }
+                    parts.push(part);
+                    index += len;
+                    start = index;
+                    continue;
+                }
+                depth += 1;
+            }
+            b'{' => depth += 1,
+            b'}' => depth = depth.saturating_sub(1),
+            _ => (),
+        }
+        index += 1;
+    }
+
+    if start < pattern.len() {
+        parts.push(Part::Text(&pattern[start..]));
+    }
+    parts
+}
+
+pub(crate) fn file_name(path: &str) -> &str {
+    path.rsplit(|c| c == '/' || c == '\\')
+        .next()
+        .unwrap_or(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{file_name, split, Part};
+
+    #[test]
+    fn split_pattern() {
+        assert_eq!(
+            split("{l} [{M} {fn}:{L}] {m}{n}"),
+            vec![
+                Part::Text("{l} ["),
+                Part::Module,
+                Part::Text(" "),
+                Part::FileName,
+                Part::Text(":{L}] {m}{n}"),
+            ]
+        );
+        assert_eq!(split("{module}"), vec![Part::Module]);
+        assert_eq!(
+            split("[{ctx}] {X(req_id)}"),
+            vec![Part::Text("["), Part::Context, Part::Text("] {X(req_id)}")]
+        );
+        assert_eq!(split("{M:>20}{Mx}"), vec![Part::Text("{M:>20}{Mx}")]);
+        assert_eq!(split("{fn}{fn}"), vec![Part::FileName, Part::FileName]);
+        assert_eq!(split("{f}"), vec![Part::Text("{f}")]);
+        assert!(split("").is_empty());
+    }
+
+    #[test]
+    fn split_pattern_ignores_escaped_and_nested_tokens() {
+        assert_eq!(split("{{fn}}"), vec![Part::Text("{{fn}}")]);
+        assert_eq!(
+            split("{{ts}} {ts}"),
+            vec![Part::Text("{{ts}} "), Part::Timestamp]
+        );
+        assert_eq!(split("{h({ts})}"), vec![Part::Text("{h({ts})}")]);
+        assert_eq!(split("{h({fn})}"), vec![Part::Text("{h({fn})}")]);
+        assert_eq!(
+            split("{h({l})} {fn}"),
+            vec![Part::Text("{h({l})} "), Part::FileName]
+        );
+    }
+
+    #[test]
+    fn strip_directories() {
+        assert_eq!(file_name("src/log/mod.rs"), "mod.rs");
+        assert_eq!(file_name("src\\log\\mod.rs"), "mod.rs");
+        assert_eq!(file_name("main.rs"), "main.rs");
+    }
+}
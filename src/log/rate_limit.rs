@@ -0,0 +1,319 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use log::LogLevel;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Opt-in suppression of repeated or excessive records by an async appender.  Both limits are
+/// disabled by default.
+///
+/// Suppressed records are summarised once the suppression ends by a record with the level, target
+/// and location of the latest suppressed one and a message like
+/// `message repeated 1234 times: ...`.  Since only the `log` macros can create records to pass to
+/// an encoder, the appender writes the summary itself in its
+/// [`Encoding`](enum.Encoding.html): in the layout of the `init*` functions, e.g.
+/// `WARN 16:10:44.989712300 [routing::core core.rs:42] message repeated 1234 times: ...`, or as a
+/// JSON or binary record with the same fields as the encoders of those encodings.  Summaries still
+/// pending when the appender is dropped are lost.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RateLimit {
+    dedup_window: Option<Duration>,
+    module_rate: Option<(u32, u32)>,
+}
+
+impl RateLimit {
+    /// Creates a rate limit which lets every record through.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Suppresses records with the same message as the previous record logged from the same call
+    /// site within `window` of it.
+    pub fn dedup_window(mut self, window: Duration) -> Self {
+        self.dedup_window = Some(window);
+        self
+    }
+
+    /// Limits the records of each module to `per_second` on average, allowing bursts of up to
+    /// `burst` records.
+    pub fn module_rate(mut self, per_second: u32, burst: u32) -> Self {
+        self.module_rate = Some((per_second, burst));
+        self
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.dedup_window.is_some() || self.module_rate.is_some()
+    }
+}
+
+/// Where and at which level a record was logged.
+pub(crate) struct CallSite<'a> {
+    pub level: LogLevel,
+    pub target: &'a str,
+    pub module: &'a str,
+    pub file: &'a str,
+    pub line: u32,
+}
+
+/// A record summarising suppressed records.
+#[derive(Debug, PartialEq)]
+pub(crate) struct Summary {
+    pub level: LogLevel,
+    pub target: String,
+    pub module: String,
+    pub file: String,
+    pub line: u32,
+    pub message: String,
+}
+
+#[derive(Debug)]
+struct Repeats {
+    level: LogLevel,
+    target: String,
+    module: String,
+    message: String,
+    since: Instant,
+    suppressed: u64,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    refilled: Instant,
+    dropped: u64,
+    // Level, target and location of the latest dropped record, for its summary.
+    level: LogLevel,
+    target: String,
+    file: String,
+    line: u32,
+}
+
+/// The state of a `RateLimit` applied to one appender.
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    config: RateLimit,
+    repeats: HashMap<(String, u32), Repeats>,
+    buckets: HashMap<String, Bucket>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimit) -> Self {
+        RateLimiter {
+            config,
+            repeats: HashMap::new(),
+            buckets: HashMap::new(),
+        }
+    }
+
+    /// Returns whether the record should be logged, adding any summaries which are now due to
+    /// `summaries`.
+    pub fn check<F: FnOnce() -> String>(
+        &mut self,
+        site: &CallSite,
+        message: F,
+        now: Instant,
+        summaries: &mut Vec<Summary>,
+    ) -> bool {
+        if let Some(window) = self.config.dedup_window {
+            self.expire_repeats(window, now, summaries);
+
+            let message = message();
+            let key = (site.file.to_owned(), site.line);
+            if let Some(repeats) = self.repeats.get_mut(&key) {
+                if repeats.message == message {
+                    repeats.suppressed += 1;
+                    return false;
+                }
+            }
+            let repeats = Repeats {
+                level: site.level,
+                target: site.target.to_owned(),
+                module: site.module.to_owned(),
+                message,
+                since: now,
+                suppressed: 0,
+            };
+            if let Some(previous) = self.repeats.insert(key, repeats) {
+                summarise_repeats(site.file, site.line, &previous, summaries);
+            }
+        }
+
+        if let Some((per_second, burst)) = self.config.module_rate {
+            let bucket = self
+                .buckets
+                .entry(site.module.to_owned())
+                .or_insert_with(|| Bucket {
+                    tokens: f64::from(burst),
+                    refilled: now,
+                    dropped: 0,
+                    level: site.level,
+                    target: String::new(),
+                    file: String::new(),
+                    line: 0,
+                });
+            let elapsed = now.duration_since(bucket.refilled);
+            let elapsed = elapsed.as_secs() as f64 + f64::from(elapsed.subsec_nanos()) * 1e-9;
+            bucket.tokens = (bucket.tokens + elapsed * f64::from(per_second)).min(f64::from(burst));
+            bucket.refilled = now;
+
+            if bucket.tokens < 1.0 {
+                bucket.dropped += 1;
+                bucket.level = site.level;
+                bucket.target.clear();
+                bucket.target.push_str(site.target);
+                bucket.file.clear();
+                bucket.file.push_str(site.file);
+                bucket.line = site.line;
+                return false;
+            }
+            bucket.tokens -= 1.0;
+            if bucket.dropped > 0 {
+                summaries.push(Summary {
+                    level: bucket.level,
+                    target: bucket.target.clone(),
+                    module: site.module.to_owned(),
+                    file: bucket.file.clone(),
+                    line: bucket.line,
+                    message: format!("{} records dropped by rate limit", bucket.dropped),
+                });
+                bucket.dropped = 0;
+            }
+        }
+
+        true
+    }
+
+    fn expire_repeats(&mut self, window: Duration, now: Instant, summaries: &mut Vec<Summary>) {
+        let expired: Vec<_> = self
+            .repeats
+            .iter()
+            .filter(|&(_, repeats)| now.duration_since(repeats.since) >= window)
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in expired {
+            if let Some(repeats) = self.repeats.remove(&key) {
+                summarise_repeats(&key.0, key.1, &repeats, summaries);
+            }
+        }
+    }
+}
+
+fn summarise_repeats(file: &str, line: u32, repeats: &Repeats, summaries: &mut Vec<Summary>) {
+    if repeats.suppressed > 0 {
+        summaries.push(Summary {
+            level: repeats.level,
+            target: repeats.target.clone(),
+            module: repeats.module.clone(),
+            file: file.to_owned(),
+            line,
+            message: format!(
+                "message repeated {} times: {}",
+                repeats.suppressed, repeats.message
+            ),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn site(module: &str, line: u32) -> CallSite {
+        CallSite {
+            level: LogLevel::Warn,
+            target: module,
+            module,
+            file: "src/routing/core.rs",
+            line,
+        }
+    }
+
+    #[test]
+    fn deduplicate() {
+        let window = Duration::from_secs(1);
+        let mut limiter = RateLimiter::new(RateLimit::new().dedup_window(window));
+        let mut summaries = Vec::new();
+        let start = Instant::now();
+        let mut check = |line, message: &str, offset_ms| {
+            let now = start + Duration::from_millis(offset_ms);
+            limiter.check(
+                &site("routing", line),
+                || message.to_owned(),
+                now,
+                &mut summaries,
+            )
+        };
+
+        assert!(check(1, "a", 0));
+        assert!(!check(1, "a", 10));
+        assert!(!check(1, "a", 20));
+        // Another call site or message isn't suppressed.
+        assert!(check(2, "a", 30));
+        assert!(check(1, "b", 40));
+        assert!(!check(1, "b", 50));
+        // The window has passed.
+        assert!(check(1, "b", 1100));
+
+        assert_eq!(
+            summaries,
+            vec![
+                Summary {
+                    level: LogLevel::Warn,
+                    target: "routing".to_owned(),
+                    module: "routing".to_owned(),
+                    file: "src/routing/core.rs".to_owned(),
+                    line: 1,
+                    message: "message repeated 2 times: a".to_owned(),
+                },
+                Summary {
+                    level: LogLevel::Warn,
+                    target: "routing".to_owned(),
+                    module: "routing".to_owned(),
+                    file: "src/routing/core.rs".to_owned(),
+                    line: 1,
+                    message: "message repeated 1 times: b".to_owned(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn limit_module_rate() {
+        let mut limiter = RateLimiter::new(RateLimit::new().module_rate(10, 2));
+        let mut summaries = Vec::new();
+        let start = Instant::now();
+        let mut check = |module, offset_ms| {
+            let now = start + Duration::from_millis(offset_ms);
+            limiter.check(&site(module, 1), String::new, now, &mut summaries)
+        };
+
+        assert!(check("routing", 0));
+        assert!(check("routing", 0));
+        assert!(!check("routing", 0));
+        assert!(!check("routing", 50));
+        assert!(check("crust", 50));
+        // One token is refilled every 100ms, ending the suppression.
+        assert!(check("routing", 100));
+        assert!(!check("routing", 100));
+        assert!(check("routing", 200));
+
+        let messages: Vec<_> = summaries
+            .iter()
+            .map(|summary| (&summary.module[..], &summary.message[..]))
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                ("routing", "2 records dropped by rate limit"),
+                ("routing", "1 records dropped by rate limit"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,466 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+//! Parsing and filtering of log files written by this crate.
+//!
+//! Both the default text format (with or without thread names) and JSON lines are understood.
+//! Text lines which don't start a new record, e.g. those of a multi-line message, are appended to
+//! the message of the preceding record.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! # #[macro_use]
+//! # extern crate unwrap;
+//! # extern crate log;
+//! # extern crate maidsafe_utilities;
+//! use log::LogLevelFilter;
+//! use maidsafe_utilities::log::reader::{Filter, LogReader};
+//! use std::fs::File;
+//! use std::io::BufReader;
+//!
+//! # fn main() {
+//! let file = BufReader::new(unwrap!(File::open("node.log")));
+//! let filter = Filter::new().level(LogLevelFilter::Warn).module("routing");
+//! for entry in LogReader::new(file).filter(filter) {
+//!     print!("{}", unwrap!(entry));
+//! }
+//! # }
+//! ```
+
+use chrono::{DateTime, FixedOffset, NaiveTime, Timelike};
+use log::{LogLevel, LogLevelFilter};
+use serde_json;
+use std::cmp::Ordering;
+use std::fmt::{self, Display, Formatter};
+use std::io::{self, BufRead, Lines};
+use std::str::FromStr;
+
+/// Format of the time-of-day timestamps written by default.
+const TIME_OF_DAY_FORMAT: &str = "%H:%M:%S%.f";
+
+/// The timestamp of a log entry.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LogTime {
+    /// Only the (local) time of day, as written by the default pattern.
+    TimeOfDay(NaiveTime),
+    /// A full date and time, as written by the JSON encoder.
+    DateTime(DateTime<FixedOffset>),
+}
+
+impl LogTime {
+    /// Returns the time of day, ignoring any date.
+    pub fn time_of_day(&self) -> NaiveTime {
+        match *self {
+            LogTime::TimeOfDay(time) => time,
+            LogTime::DateTime(date_time) => date_time.time(),
+        }
+    }
+
+    /// Compares two timestamps.  If either of them lacks a date, only the times of day are
+    /// compared.
+    pub fn compare(&self, other: &LogTime) -> Ordering {
+        match (*self, *other) {
+            (LogTime::DateTime(lhs), LogTime::DateTime(rhs)) => lhs.cmp(&rhs),
+            (lhs, rhs) => lhs.time_of_day().cmp(&rhs.time_of_day()),
+        }
+    }
+}
+
+impl FromStr for LogTime {
+    type Err = String;
+
+    fn from_str(time: &str) -> Result<Self, String> {
+        if let Ok(date_time) = DateTime::parse_from_rfc3339(time) {
+            return Ok(LogTime::DateTime(date_time));
+        }
+        NaiveTime::parse_from_str(time, TIME_OF_DAY_FORMAT)
+            .map(LogTime::TimeOfDay)
+            .map_err(|_| format!("Invalid log time: {}", time))
+    }
+}
+
+impl Display for LogTime {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        match *self {
+            LogTime::TimeOfDay(time) => write!(
+                formatter,
+                "{}.{:09}",
+                time.format("%H:%M:%S"),
+                time.nanosecond()
+            ),
+            LogTime::DateTime(date_time) => write!(formatter, "{}", date_time.to_rfc3339()),
+        }
+    }
+}
+
+/// A single parsed log record.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LogEntry {
+    /// Level of the record.
+    pub level: LogLevel,
+    /// When the record was logged.
+    pub time: LogTime,
+    /// Name of the logging thread, if present in the log.
+    pub thread: Option<String>,
+    /// Module path of the log statement.
+    pub module: String,
+    /// Source file of the log statement.
+    pub file: String,
+    /// Line number of the log statement.
+    pub line: u32,
+    /// The message, which may span several lines.
+    pub message: String,
+}
+
+impl LogEntry {
+    /// Parses a single line in either the default text format or as JSON.  Returns `None` if the
+    /// line doesn't start a new record.
+    pub fn parse(line: &str) -> Option<LogEntry> {
+        if line.starts_with('{') {
+            parse_json(line)
+        } else {
+            parse_text(line)
+        }
+    }
+}
+
+impl Display for LogEntry {
+    /// Formats the entry in the default text format, including the trailing newline.
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(formatter, "{} {}", self.level, self.time)?;
+        if let Some(ref thread) = self.thread {
+            write!(formatter, " {}", thread)?;
+        }
+        writeln!(
+            formatter,
+            " [{} {}:{}] {}",
+            self.module, self.file, self.line, self.message
+        )
+    }
+}
+
+fn parse_text(line: &str) -> Option<LogEntry> {
+    let mut parts = line.splitn(3, ' ');
+    let level = LogLevel::from_str(parts.next()?).ok()?;
+    let time = parts.next()?.parse().ok()?;
+    let rest = parts.next()?;
+
+    // The thread name (which may contain spaces) precedes the first `[module file:line] ` section.
+    let mut search_from = 0;
+    loop {
+        let open = search_from + rest[search_from..].find('[')?;
+        if open == 0 || rest[..open].ends_with(' ') {
+            if let Some(entry) = parse_location(&rest[open..]) {
+                let (module, file, line, message) = entry;
+                let thread = if open == 0 {
+                    None
+                } else {
+                    Some(rest[..open - 1].to_owned())
+                };
+                return Some(LogEntry {
+                    level,
+                    time,
+                    thread,
+                    module: module.to_owned(),
+                    file: file.to_owned(),
+                    line,
+                    message: message.to_owned(),
+                });
+            }
+        }
+        search_from = open + 1;
+    }
+}
+
+/// Parses `[module file:line] message`.
+fn parse_location(input: &str) -> Option<(&str, &str, u32, &str)> {
+    let close = input.find("] ").or_else(|| {
+        if input.ends_with(']') {
+            Some(input.len() - 1)
+        } else {
+            None
+        }
+    })?;
+    let location = &input[1..close];
+    let space = location.find(' ')?;
+    let (module, file_and_line) = (&location[..space], &location[space + 1..]);
+    if module.is_empty() || file_and_line.contains(' ') {
+        return None;
+    }
+    let colon = file_and_line.rfind(':')?;
+    let line = file_and_line[colon + 1..].parse().ok()?;
+    let message = input.get(close + 2..).unwrap_or("");
+    Some((module, &file_and_line[..colon], line, message))
+}
+
+#[derive(Deserialize)]
+struct JsonEntry {
+    time: String,
+    message: String,
+    module_path: String,
+    file: String,
+    line: u32,
+    level: String,
+    thread: Option<String>,
+}
+
+fn parse_json(line: &str) -> Option<LogEntry> {
+    let entry: JsonEntry = serde_json::from_str(line).ok()?;
+    Some(LogEntry {
+        level: LogLevel::from_str(&entry.level).ok()?,
+        time: entry.time.parse().ok()?,
+        thread: entry.thread,
+        module: entry.module_path,
+        file: entry.file,
+        line: entry.line,
+        message: entry.message,
+    })
+}
+
+/// Criteria for selecting log entries.  All criteria which are set must match.
+#[derive(Clone, Debug, Default)]
+pub struct Filter {
+    level: Option<LogLevelFilter>,
+    module: Option<String>,
+    thread: Option<String>,
+    since: Option<LogTime>,
+    until: Option<LogTime>,
+}
+
+impl Filter {
+    /// Creates a filter matching every entry.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Only match entries at least as severe as `level`.
+    pub fn level(mut self, level: LogLevelFilter) -> Self {
+        self.level = Some(level);
+        self
+    }
+
+    /// Only match entries logged from `module` or its submodules.
+    pub fn module<S: Into<String>>(mut self, module: S) -> Self {
+        self.module = Some(module.into());
+        self
+    }
+
+    /// Only match entries logged by the thread called `thread`.
+    pub fn thread<S: Into<String>>(mut self, thread: S) -> Self {
+        self.thread = Some(thread.into());
+        self
+    }
+
+    /// Only match entries logged at or after `time`.
+    pub fn since(mut self, time: LogTime) -> Self {
+        self.since = Some(time);
+        self
+    }
+
+    /// Only match entries logged before `time`.
+    pub fn until(mut self, time: LogTime) -> Self {
+        self.until = Some(time);
+        self
+    }
+
+    /// Returns whether `entry` meets all the criteria of this filter.
+    ///
+    /// If only times of day are compared and `since` is later than `until`, the window is taken to
+    /// wrap around midnight.
+    pub fn matches(&self, entry: &LogEntry) -> bool {
+        if let Some(level) = self.level {
+            if entry.level > level {
+                return false;
+            }
+        }
+        if let Some(ref module) = self.module {
+            if !(entry.module == *module || entry.module.starts_with(&format!("{}::", module))) {
+                return false;
+            }
+        }
+        if let Some(ref thread) = self.thread {
+            if entry.thread.as_ref() != Some(thread) {
+                return false;
+            }
+        }
+        self.in_window(&entry.time)
+    }
+
+    fn in_window(&self, time: &LogTime) -> bool {
+        let after_start = match self.since {
+            Some(since) => time.compare(&since) != Ordering::Less,
+            None => true,
+        };
+        let before_end = match self.until {
+            Some(until) => time.compare(&until) == Ordering::Less,
+            None => true,
+        };
+        match (self.since, self.until) {
+            (Some(since), Some(until)) if since.compare(&until) == Ordering::Greater => {
+                after_start || before_end
+            }
+            _ => after_start && before_end,
+        }
+    }
+}
+
+/// Iterator over the entries of a log, merging continuation lines into the preceding entry.
+pub struct LogReader<R> {
+    lines: Lines<R>,
+    pending: Option<LogEntry>,
+    filter: Filter,
+}
+
+impl<R: BufRead> LogReader<R> {
+    /// Reads entries from `reader`.
+    pub fn new(reader: R) -> Self {
+        LogReader {
+            lines: reader.lines(),
+            pending: None,
+            filter: Filter::new(),
+        }
+    }
+
+    /// Only yields entries matching `filter`.
+    pub fn filter(mut self, filter: Filter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    fn next_entry(&mut self) -> Option<io::Result<LogEntry>> {
+        loop {
+            match self.lines.next() {
+                Some(Ok(line)) => match LogEntry::parse(&line) {
+                    Some(entry) => {
+                        if let Some(previous) = self.pending.replace(entry) {
+                            return Some(Ok(previous));
+                        }
+                    }
+                    None => {
+                        if let Some(ref mut pending) = self.pending {
+                            pending.message.push('\n');
+                            pending.message.push_str(&line);
+                        }
+                    }
+                },
+                Some(Err(error)) => return Some(Err(error)),
+                None => return self.pending.take().map(Ok),
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for LogReader<R> {
+    type Item = io::Result<LogEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.next_entry()? {
+                Ok(entry) => {
+                    if self.filter.matches(&entry) {
+                        return Some(Ok(entry));
+                    }
+                }
+                Err(error) => return Some(Err(error)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOG: &str = "\
+WARN 19:33:49.245434200 main [example::my_mod main.rs:10] A warning
+INFO 19:33:49.250000000 <unnamed> [example main.rs:19] Message in unnamed thread
+ERROR 19:33:50.000000001 Worker pool 1 [example::pool pool.rs:22] Multi-line [message]
+second line
+DEBUG 19:33:51.000000000 [example::my_mod main.rs:12] No thread name
+{\"time\":\"2018-06-01T19:33:52.5+01:00\",\"message\":\"From JSON\",\"module_path\":\"example\",\
+\"file\":\"src/main.rs\",\"line\":30,\"level\":\"TRACE\",\"target\":\"example\",\"thread\":null,\
+\"mdc\":{}}
+";
+
+    fn entries(filter: Filter) -> Vec<LogEntry> {
+        LogReader::new(LOG.as_bytes())
+            .filter(filter)
+            .map(|entry| unwrap!(entry))
+            .collect()
+    }
+
+    fn time(time: &str) -> LogTime {
+        unwrap!(time.parse())
+    }
+
+    #[test]
+    fn parse_entries() {
+        let entries = entries(Filter::new());
+        assert_eq!(entries.len(), 5);
+
+        assert_eq!(entries[0].level, LogLevel::Warn);
+        assert_eq!(entries[0].time, time("19:33:49.245434200"));
+        assert_eq!(entries[0].thread, Some("main".to_owned()));
+        assert_eq!(entries[0].module, "example::my_mod");
+        assert_eq!(entries[0].file, "main.rs");
+        assert_eq!(entries[0].line, 10);
+        assert_eq!(entries[0].message, "A warning");
+        assert_eq!(
+            entries[0].to_string(),
+            "WARN 19:33:49.245434200 main [example::my_mod main.rs:10] A warning\n"
+        );
+
+        assert_eq!(entries[2].thread, Some("Worker pool 1".to_owned()));
+        assert_eq!(entries[2].message, "Multi-line [message]\nsecond line");
+
+        assert_eq!(entries[3].thread, None);
+        assert_eq!(entries[3].message, "No thread name");
+
+        assert_eq!(entries[4].level, LogLevel::Trace);
+        assert_eq!(entries[4].time, time("2018-06-01T19:33:52.5+01:00"));
+        assert_eq!(entries[4].file, "src/main.rs");
+        assert_eq!(entries[4].message, "From JSON");
+    }
+
+    #[test]
+    fn filter_entries() {
+        let warnings = entries(Filter::new().level(LogLevelFilter::Warn));
+        assert_eq!(warnings.len(), 2);
+
+        let my_mod = entries(Filter::new().module("example::my_mod"));
+        assert_eq!(my_mod.len(), 2);
+        assert_eq!(entries(Filter::new().module("example")).len(), 5);
+        assert!(entries(Filter::new().module("exam")).is_empty());
+
+        let worker = entries(Filter::new().thread("Worker pool 1"));
+        assert_eq!(worker.len(), 1);
+
+        let window = entries(
+            Filter::new()
+                .since(time("19:33:49.25"))
+                .until(time("19:33:52")),
+        );
+        assert_eq!(window.len(), 3);
+        let wrapped = entries(
+            Filter::new()
+                .since(time("19:33:51"))
+                .until(time("19:33:49.25")),
+        );
+        assert_eq!(wrapped.len(), 3);
+    }
+
+    #[test]
+    fn reject_non_records() {
+        assert!(LogEntry::parse("").is_none());
+        assert!(LogEntry::parse("second line").is_none());
+        assert!(LogEntry::parse("WARN 19:33:49 no location").is_none());
+        assert!(LogEntry::parse("{\"not\": \"a record\"}").is_none());
+    }
+}
@@ -0,0 +1,175 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use regex::bytes::Regex;
+use std::cmp;
+use std::sync::{Arc, RwLock};
+
+lazy_static! {
+    static ref REDACTION: RwLock<Arc<Redaction>> = RwLock::new(Arc::new(Redaction::new()));
+}
+
+/// Matches absolute Unix paths with at least two components, capturing just the path.
+const UNIX_PATH: &str = r#"(?:^|[\s"'=(\[])(/[\w.-]+(?:/[\w.-]+)+)"#;
+
+/// Matches absolute Windows paths.
+const WINDOWS_PATH: &str = r#"(?-u:\b)[A-Za-z]:\\[^\s"']+"#;
+
+/// Character replacing each byte of redacted text.
+const MASK: u8 = b'*';
+
+/// Rules for redacting sensitive values from log output.  Word boundaries in the built-in detectors
+/// are ASCII-only, as binary records may hold invalid UTF-8.
+///
+/// Every byte of a match is replaced by `*`, so the length of each record is preserved.  If a
+/// pattern has a capture group, only the text matched by the first group is redacted.
+///
+/// Records of appenders with the binary [`Encoding`](enum.Encoding.html) aren't redacted, since a
+/// match could span the encoded numbers and lengths as well as the text.
+#[derive(Clone, Debug, Default)]
+pub struct Redaction {
+    rules: Vec<Regex>,
+}
+
+impl Redaction {
+    /// Creates an empty set of rules, redacting nothing.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Redacts text matching the regular expression `pattern`.
+    pub fn pattern(mut self, pattern: &str) -> Result<Self, String> {
+        let regex = Regex::new(pattern).map_err(|e| format!("Invalid redaction pattern: {}", e))?;
+        self.rules.push(regex);
+        Ok(self)
+    }
+
+    /// Redacts runs of at least `min_len` hexadecimal digits including at least one letter, e.g.
+    /// keys and hashes.  Runs of decimal digits, e.g. fractional seconds or ports, are kept.
+    pub fn hex_keys(self, min_len: usize) -> Self {
+        // The regex crate has no lookahead, so there is an alternative for each number of decimal
+        // digits preceding the first letter.
+        let min_len = cmp::max(min_len, 1);
+        let mut alternatives = vec![format!("[a-fA-F][0-9a-fA-F]{{{},}}", min_len - 1)];
+        alternatives.extend((1..min_len).map(|digits| {
+            format!(
+                "[0-9]{{{}}}[a-fA-F][0-9a-fA-F]{{{},}}",
+                digits,
+                min_len - 1 - digits
+            )
+        }));
+        alternatives.push(format!("[0-9]{{{},}}[a-fA-F][0-9a-fA-F]*", min_len));
+        unwrap!(self.pattern(&format!(r"(?-u:\b)(?:{})(?-u:\b)", alternatives.join("|"))))
+    }
+
+    /// Redacts absolute file paths.
+    pub fn paths(self) -> Self {
+        unwrap!(unwrap!(self.pattern(UNIX_PATH)).pattern(WINDOWS_PATH))
+    }
+
+    /// Masks all matches of the rules in `msg`.
+    pub fn apply(&self, msg: &mut [u8]) {
+        let mut ranges = Vec::new();
+        for rule in &self.rules {
+            for captures in rule.captures_iter(msg) {
+                if let Some(found) = captures.get(1).or_else(|| captures.get(0)) {
+                    ranges.push((found.start(), found.end()));
+                }
+            }
+        }
+        for (start, end) in ranges {
+            for byte in &mut msg[start..end] {
+                *byte = MASK;
+            }
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+}
+
+/// Sets the redaction rules applied by all async appenders which haven't opted out via their
+/// builder's `redact(false)` or `redact = false` in `log.toml`.
+///
+/// The rules are applied in each appender's background thread, just before a record is written.
+/// They can be changed at any time, and take effect for records written from then on.
+pub fn set_redaction(redaction: Redaction) {
+    *unwrap!(REDACTION.write()) = Arc::new(redaction);
+}
+
+/// Applies the current redaction rules to `msg`.
+pub(crate) fn redact(msg: &mut [u8]) {
+    let redaction = Arc::clone(&*unwrap!(REDACTION.read()));
+    if !redaction.is_empty() {
+        redaction.apply(msg);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(redaction: &Redaction, msg: &str) -> String {
+        let mut msg = msg.as_bytes().to_vec();
+        redaction.apply(&mut msg);
+        unwrap!(String::from_utf8(msg))
+    }
+
+    #[test]
+    fn hex_keys() {
+        let redaction = Redaction::new().hex_keys(8);
+        assert_eq!(
+            apply(&redaction, "Key 0123abcdEF ok, short ab12, id=deadbeef00."),
+            "Key ********** ok, short ab12, id=**********."
+        );
+        assert_eq!(apply(&redaction, "xdeadbeef00"), "xdeadbeef00");
+        assert_eq!(
+            apply(&redaction, "16:10:44.989712300 port 123456789"),
+            "16:10:44.989712300 port 123456789"
+        );
+        assert_eq!(
+            apply(&redaction, "a1234567 1234567a 12345678a"),
+            "******** ******** *********"
+        );
+        assert_eq!(apply(&redaction, "abcdef1"), "abcdef1");
+    }
+
+    #[test]
+    fn paths() {
+        let redaction = Redaction::new().paths();
+        assert_eq!(
+            apply(&redaction, "Opened /home/user/.ssh/id_rsa and \"/tmp/x\""),
+            "Opened ********************** and \"******\""
+        );
+        assert_eq!(
+            apply(&redaction, r"Opened C:\Users\user\key.txt"),
+            "Opened *********************"
+        );
+        assert_eq!(
+            apply(&redaction, "[routing src/core.rs:10] /root"),
+            "[routing src/core.rs:10] /root"
+        );
+    }
+
+    #[test]
+    fn patterns() {
+        let redaction = unwrap!(Redaction::new().pattern("token=([^ ]+)"));
+        assert_eq!(apply(&redaction, "token=abc123 sent"), "token=****** sent");
+        assert!(Redaction::new().pattern("(").is_err());
+    }
+
+    #[test]
+    fn binary_is_preserved() {
+        let redaction = Redaction::new().hex_keys(4);
+        let mut msg = vec![0xff, 4, b' ', b'a', b'b', b'c', b'd', b' ', 0x80];
+        redaction.apply(&mut msg);
+        assert_eq!(msg, vec![0xff, 4, b' ', b'*', b'*', b'*', b'*', b' ', 0x80]);
+    }
+}
@@ -0,0 +1,167 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use log::LogLevel;
+use rand::{self, Rng};
+use std::fmt::{self, Debug, Formatter};
+
+/// How many of the records matched by a sampling rule are kept.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SampleRate {
+    /// Keeps the first record and every `n`th one after it.
+    OneIn(u32),
+    /// Keeps each record with the given probability, from 0.0 to 1.0.
+    Probability(f64),
+}
+
+#[derive(Debug)]
+struct Rule {
+    module: String,
+    level: LogLevel,
+    rate: SampleRate,
+    seen: u64,
+}
+
+impl Rule {
+    fn matches(&self, level: LogLevel, module: &str) -> bool {
+        level == self.level
+            && (self.module.is_empty()
+                || module == self.module
+                || (module.starts_with(&self.module)
+                    && module[self.module.len()..].starts_with("::")))
+    }
+}
+
+/// Sampling of the records of an async appender, applied before they are encoded.
+///
+/// Each rule applies to records of exactly one level from a module and its submodules, or from
+/// all modules if the module is empty.  Where several rules match a record, the one with the
+/// longest module wins.  Records not matched by any rule are all kept.
+pub struct Sampling {
+    rules: Vec<Rule>,
+    rng: Box<Rng + Send>,
+}
+
+impl Sampling {
+    /// Creates a sampling without rules, i.e. keeping every record.
+    pub fn new() -> Self {
+        Sampling {
+            rules: Vec::new(),
+            rng: Box::new(rand::weak_rng()),
+        }
+    }
+
+    /// Adds a rule sampling the records at `level` from `module` at the given `rate`.
+    pub fn rule<S: Into<String>>(mut self, module: S, level: LogLevel, rate: SampleRate) -> Self {
+        self.rules.push(Rule {
+            module: module.into(),
+            level,
+            rate,
+            seen: 0,
+        });
+        self
+    }
+
+    /// Sets the random number generator used for `SampleRate::Probability`, e.g. a
+    /// [`SeededRng`](../struct.SeededRng.html) to make the sampled output reproducible in tests.
+    pub fn rng<R: 'static + Rng + Send>(mut self, rng: R) -> Self {
+        self.rng = Box::new(rng);
+        self
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        !self.rules.is_empty()
+    }
+
+    /// Returns whether the record should be kept.
+    pub(crate) fn keep(&mut self, level: LogLevel, module: &str) -> bool {
+        let rule = self
+            .rules
+            .iter_mut()
+            .filter(|rule| rule.matches(level, module))
+            .max_by_key(|rule| rule.module.len());
+        let rule = match rule {
+            Some(rule) => rule,
+            None => return true,
+        };
+
+        match rule.rate {
+            SampleRate::OneIn(n) => {
+                let keep = n <= 1 || rule.seen % u64::from(n) == 0;
+                rule.seen += 1;
+                keep
+            }
+            SampleRate::Probability(probability) => self.rng.next_f64() < probability,
+        }
+    }
+}
+
+impl Default for Sampling {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Debug for Sampling {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        formatter
+            .debug_struct("Sampling")
+            .field("rules", &self.rules)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SeededRng;
+
+    #[test]
+    fn one_in_n() {
+        let mut sampling = Sampling::new()
+            .rule("routing", LogLevel::Trace, SampleRate::OneIn(3))
+            .rule("routing::core", LogLevel::Trace, SampleRate::OneIn(1))
+            .rule("", LogLevel::Debug, SampleRate::OneIn(2));
+
+        let kept: Vec<_> = (0..7)
+            .map(|_| sampling.keep(LogLevel::Trace, "routing::peer"))
+            .collect();
+        assert_eq!(kept, vec![true, false, false, true, false, false, true]);
+
+        // The most specific rule applies.
+        assert!((0..5).all(|_| sampling.keep(LogLevel::Trace, "routing::core")));
+        // Rules apply to a single level and to submodules only.
+        assert!((0..5).all(|_| sampling.keep(LogLevel::Info, "routing")));
+        assert!((0..5).all(|_| sampling.keep(LogLevel::Trace, "routingtable")));
+        // An empty module matches all modules.
+        assert!(sampling.keep(LogLevel::Debug, "crust"));
+        assert!(!sampling.keep(LogLevel::Debug, "routing"));
+    }
+
+    #[test]
+    fn probability() {
+        let sample = || {
+            let mut sampling = Sampling::new()
+                .rule("", LogLevel::Trace, SampleRate::Probability(0.25))
+                .rng(SeededRng::new());
+            (0..1000)
+                .map(|_| sampling.keep(LogLevel::Trace, "routing"))
+                .collect::<Vec<_>>()
+        };
+
+        let kept = sample();
+        // All `SeededRng`s share the process-wide seed, so the sampling is reproducible.
+        assert_eq!(kept, sample());
+        let count = kept.iter().filter(|&&keep| keep).count();
+        assert!(count > 150 && count < 350, "{}", count);
+
+        let mut never = Sampling::new().rule("", LogLevel::Trace, SampleRate::Probability(0.0));
+        assert!(!never.keep(LogLevel::Trace, "routing"));
+    }
+}
@@ -0,0 +1,207 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use crate::thread::{self, Joiner};
+use log::LogLevel;
+use std::fmt::{self, Display, Formatter};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
+
+lazy_static! {
+    static ref COUNTERS: Mutex<Vec<Weak<Counters>>> = Mutex::new(Vec::new());
+}
+
+/// The live counters of one async appender, shared between it and its background thread.
+#[derive(Debug, Default)]
+pub(crate) struct Counters {
+    sink: String,
+    enqueued: AtomicUsize,
+    written: AtomicUsize,
+    failed: AtomicUsize,
+    dropped: AtomicUsize,
+    bytes_written: AtomicUsize,
+    reconnect_attempts: AtomicUsize,
+}
+
+impl Counters {
+    /// Creates counters for an appender writing to `sink`, and registers them for `stats`.
+    pub fn register(sink: String) -> Arc<Self> {
+        let counters = Arc::new(Counters {
+            sink,
+            ..Default::default()
+        });
+        let mut registry = unwrap!(COUNTERS.lock());
+        registry.retain(|counters| counters.upgrade().is_some());
+        registry.push(Arc::downgrade(&counters));
+        counters
+    }
+
+    pub fn enqueued(&self) {
+        let _ = self.enqueued.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn written(&self, bytes: usize) {
+        let _ = self.bytes_written.fetch_add(bytes, Ordering::Relaxed);
+        let _ = self.written.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn failed(&self) {
+        let _ = self.failed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn dropped(&self) {
+        let _ = self.dropped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn set_reconnect_attempts(&self, attempts: usize) {
+        self.reconnect_attempts.store(attempts, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> AppenderStats {
+        let written = self.written.load(Ordering::Relaxed);
+        let failed = self.failed.load(Ordering::Relaxed);
+        let enqueued = self.enqueued.load(Ordering::Relaxed);
+        AppenderStats {
+            sink: self.sink.clone(),
+            enqueued,
+            written,
+            failed,
+            dropped: self.dropped.load(Ordering::Relaxed),
+            queue_depth: enqueued.saturating_sub(written + failed),
+            bytes_written: self.bytes_written.load(Ordering::Relaxed),
+            reconnect_attempts: self.reconnect_attempts.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// A snapshot of the counters of one async appender.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AppenderStats {
+    /// Description of where the appender writes to, e.g. `file:/var/log/node.log`.
+    pub sink: String,
+    /// Records handed to the background thread, including summaries of suppressed records.
+    pub enqueued: usize,
+    /// Records written successfully.
+    pub written: usize,
+    /// Records which could not be written.
+    pub failed: usize,
+    /// Records dropped by sampling, de-duplication or rate limiting, or because they could not be
+    /// encoded or queued.
+    pub dropped: usize,
+    /// Records waiting to be written.
+    pub queue_depth: usize,
+    /// Bytes written successfully.
+    pub bytes_written: usize,
+    /// Attempts to reconnect to a web socket server.
+    pub reconnect_attempts: usize,
+}
+
+impl Display for AppenderStats {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(
+            formatter,
+            "{}: enqueued {}, written {}, failed {}, dropped {}, queued {}, bytes written {}, \
+             reconnect attempts {}",
+            self.sink,
+            self.enqueued,
+            self.written,
+            self.failed,
+            self.dropped,
+            self.queue_depth,
+            self.bytes_written,
+            self.reconnect_attempts
+        )
+    }
+}
+
+/// Returns a snapshot of the counters of every live async appender, in order of creation.
+pub fn stats() -> Vec<AppenderStats> {
+    unwrap!(COUNTERS.lock())
+        .iter()
+        .filter_map(Weak::upgrade)
+        .map(|counters| counters.snapshot())
+        .collect()
+}
+
+/// Logs the counters of every async appender at `level` once per `interval`, until the returned
+/// handle is dropped.
+///
+/// The records have the target `maidsafe_utilities::log::stats`, so they are only written if the
+/// logger for that target, or the root logger, allows `level`.  With the default root level of
+/// `Warn`, pass `LogLevel::Warn` or configure a logger for the target.
+pub fn report_stats(interval: Duration, level: LogLevel) -> StatsReporter {
+    let (stop_tx, stop_rx) = mpsc::channel();
+    let joiner = thread::named("LogStats", move || loop {
+        match stop_rx.recv_timeout(interval) {
+            Err(RecvTimeoutError::Timeout) => {
+                for appender_stats in stats() {
+                    log!(level, "{}", appender_stats);
+                }
+            }
+            Ok(()) | Err(RecvTimeoutError::Disconnected) => break,
+        }
+    });
+    StatsReporter {
+        stop_tx,
+        _raii_joiner: joiner,
+    }
+}
+
+/// Handle to the thread started by [`report_stats`](fn.report_stats.html), which is stopped when
+/// this is dropped.
+pub struct StatsReporter {
+    stop_tx: Sender<()>,
+    _raii_joiner: Joiner,
+}
+
+impl Drop for StatsReporter {
+    fn drop(&mut self) {
+        let _ = self.stop_tx.send(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshot() {
+        let counters = Counters::register("test:snapshot".to_owned());
+        for _ in 0..5 {
+            counters.enqueued();
+        }
+        counters.written(10);
+        counters.written(20);
+        counters.failed();
+        counters.dropped();
+        counters.set_reconnect_attempts(3);
+
+        let snapshot = unwrap!(stats()
+            .into_iter()
+            .find(|stats| stats.sink == "test:snapshot"));
+        assert_eq!(
+            snapshot,
+            AppenderStats {
+                sink: "test:snapshot".to_owned(),
+                enqueued: 5,
+                written: 2,
+                failed: 1,
+                dropped: 1,
+                queue_depth: 2,
+                bytes_written: 30,
+                reconnect_attempts: 3,
+            }
+        );
+
+        drop(counters);
+        assert!(stats().iter().all(|stats| stats.sink != "test:snapshot"));
+    }
+}
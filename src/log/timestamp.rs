@@ -0,0 +1,188 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use chrono::{DateTime, Local, UTC};
+use std::str::FromStr;
+
+/// Token in `log.toml` patterns which is replaced by the configured timestamp.
+pub const TIMESTAMP_TOKEN: &str = "{ts}";
+
+/// Number of fractional digits written for the seconds of a timestamp.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimestampPrecision {
+    /// Whole seconds.
+    Seconds,
+    /// Milliseconds.
+    Millis,
+    /// Microseconds.
+    Micros,
+    /// Nanoseconds.
+    Nanos,
+}
+
+impl FromStr for TimestampPrecision {
+    type Err = ();
+
+    fn from_str(precision: &str) -> Result<Self, ()> {
+        match &precision.to_lowercase()[..] {
+            "s" | "seconds" => Ok(TimestampPrecision::Seconds),
+            "ms" | "millis" => Ok(TimestampPrecision::Millis),
+            "us" | "micros" => Ok(TimestampPrecision::Micros),
+            "ns" | "nanos" => Ok(TimestampPrecision::Nanos),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Format of the timestamps written by the `init*` functions, and of the `{ts}` token in
+/// `log.toml` patterns.
+///
+/// The default is the local time of day in nanoseconds without a date, e.g.
+/// `16:10:44.989712300`.  Enabling `date` writes an ISO-8601 date and time including the offset,
+/// e.g. `2018-06-01T16:10:44.989712300+01:00`, or with a trailing `Z` in UTC.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimestampFormat {
+    date: bool,
+    utc: bool,
+    precision: TimestampPrecision,
+}
+
+impl TimestampFormat {
+    /// Creates the default format.
+    pub fn new() -> Self {
+        TimestampFormat {
+            date: false,
+            utc: false,
+            precision: TimestampPrecision::Nanos,
+        }
+    }
+
+    /// Whether to write the full ISO-8601 date and time rather than just the time of day.
+    pub fn date(mut self, date: bool) -> Self {
+        self.date = date;
+        self
+    }
+
+    /// Whether to write the time in UTC rather than local time.
+    pub fn utc(mut self, utc: bool) -> Self {
+        self.utc = utc;
+        self
+    }
+
+    /// Sets the precision of the seconds.
+    pub fn precision(mut self, precision: TimestampPrecision) -> Self {
+        self.precision = precision;
+        self
+    }
+
+    /// Returns the log4rs pattern formatter writing timestamps in this format.
+    pub(crate) fn pattern(&self) -> String {
+        let zone = if self.utc { "utc" } else { "local" };
+        format!("{{d({})({})}}", self.chrono_format(), zone)
+    }
+
+    /// Formats `time` in this format.
+    pub(crate) fn format(&self, time: DateTime<Local>) -> String {
+        if self.utc {
+            time.with_timezone(&UTC)
+                .format(&self.chrono_format())
+                .to_string()
+        } else {
+            time.format(&self.chrono_format()).to_string()
+        }
+    }
+
+    fn chrono_format(&self) -> String {
+        let fraction = match self.precision {
+            TimestampPrecision::Seconds => "",
+            TimestampPrecision::Millis => "%.3f",
+            TimestampPrecision::Micros => "%.6f",
+            // Equivalent to `.%f`, which has always been used by the default pattern.
+            TimestampPrecision::Nanos => "%.9f",
+        };
+        let offset = if self.utc { "Z" } else { "%:z" };
+        if self.date {
+            format!("%Y-%m-%dT%H:%M:%S{}{}", fraction, offset)
+        } else {
+            format!("%H:%M:%S{}", fraction)
+        }
+    }
+}
+
+impl Default for TimestampFormat {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::log::reader::LogTime;
+    use chrono::TimeZone;
+
+    #[test]
+    fn patterns() {
+        assert_eq!(TimestampFormat::new().pattern(), "{d(%H:%M:%S%.9f)(local)}");
+        assert_eq!(
+            TimestampFormat::new()
+                .date(true)
+                .utc(true)
+                .precision(TimestampPrecision::Millis)
+                .pattern(),
+            "{d(%Y-%m-%dT%H:%M:%S%.3fZ)(utc)}"
+        );
+        assert_eq!(
+            TimestampFormat::new()
+                .date(true)
+                .precision(TimestampPrecision::Seconds)
+                .pattern(),
+            "{d(%Y-%m-%dT%H:%M:%S%:z)(local)}"
+        );
+    }
+
+    #[test]
+    fn format() {
+        let time = UTC.ymd(2018, 6, 1).and_hms_nano(15, 10, 44, 989_712_300);
+        let local = time.with_timezone(&Local);
+        assert_eq!(
+            TimestampFormat::new().format(local),
+            local.format("%H:%M:%S%.9f").to_string()
+        );
+        assert_eq!(
+            TimestampFormat::new()
+                .date(true)
+                .utc(true)
+                .precision(TimestampPrecision::Millis)
+                .format(local),
+            "2018-06-01T15:10:44.989Z"
+        );
+    }
+
+    #[test]
+    fn timestamps_are_readable() {
+        let now = Local::now();
+        let times = vec![
+            now.format("%H:%M:%S%.9f").to_string(),
+            now.format("%H:%M:%S").to_string(),
+            now.format("%Y-%m-%dT%H:%M:%S%.6f%:z").to_string(),
+            UTC::now().format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string(),
+        ];
+        for time in times {
+            assert!(time.parse::<LogTime>().is_ok(), "{}", time);
+        }
+    }
+
+    #[test]
+    fn parse_precision() {
+        assert_eq!("ms".parse(), Ok(TimestampPrecision::Millis));
+        assert_eq!("Nanos".parse(), Ok(TimestampPrecision::Nanos));
+        assert_eq!("minutes".parse::<TimestampPrecision>(), Err(()));
+    }
+}
@@ -38,6 +38,7 @@ pub struct WebSocket {
     socket: ws::Result<(ws::Sender, Joiner)>,
     last_reconnect: Instant,
     message_buffer: VecDeque<Message>,
+    reconnect_attempts: usize,
 }
 
 impl WebSocket {
@@ -54,6 +55,7 @@ impl WebSocket {
             socket,
             last_reconnect,
             message_buffer: VecDeque::new(),
+            reconnect_attempts: 0,
         }
     }
 
@@ -75,6 +77,7 @@ impl WebSocket {
 
                 if now - self.last_reconnect >= Duration::from_secs(RECONNECT_PERIOD) {
                     self.last_reconnect = now;
+                    self.reconnect_attempts += 1;
                     self.socket = Self::connect(self.url.clone(), self.session_id.clone());
                 }
 
@@ -87,6 +90,11 @@ impl WebSocket {
         Ok(())
     }
 
+    /// Returns how often reconnecting to the server has been attempted.
+    pub fn reconnect_attempts(&self) -> usize {
+        self.reconnect_attempts
+    }
+
     fn get_sender(&self) -> ws::Result<&ws::Sender> {
         self.socket
             .as_ref()
@@ -7,8 +7,137 @@
 // specific language governing permissions and limitations relating to use of the SAFE Network
 // Software.
 
-/// This macro will panic with the given message if the "testing" feature is enabled and the calling
-/// thread is not already panicking, otherwise it will simply log an error message.
+use std::collections::BTreeMap;
+use std::fmt::{self, Display, Formatter};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, RwLock};
+
+static FORCED: AtomicBool = AtomicBool::new(false);
+
+type Callback = Arc<Fn(&LogOrPanicSite) + Send + Sync>;
+type SiteKey = (&'static str, u32, u32);
+
+lazy_static! {
+    static ref SITES: Mutex<BTreeMap<SiteKey, LogOrPanicSite>> = Mutex::new(BTreeMap::new());
+    static ref CALLBACK: RwLock<Option<Callback>> = RwLock::new(None);
+}
+
+/// A call site of the `log_or_panic` family of macros, with the number of times it was hit.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LogOrPanicSite {
+    /// Module containing the call.
+    pub module: &'static str,
+    /// File containing the call.
+    pub file: &'static str,
+    /// Line of the call.
+    pub line: u32,
+    /// Column of the call.
+    pub column: u32,
+    /// Number of occurrences since the process started.
+    pub count: u64,
+    /// Message of the latest occurrence.
+    pub last_message: String,
+}
+
+impl Display for LogOrPanicSite {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(
+            formatter,
+            "[{} {}:{}:{}] occurred {} times, last: {}",
+            self.module, self.file, self.line, self.column, self.count, self.last_message
+        )
+    }
+}
+
+/// Returns every call site of the `log_or_panic` family of macros hit so far, ordered by file,
+/// line and column, e.g. for alerting on "should never happen" events in deployed nodes.
+pub fn log_or_panic_sites() -> Vec<LogOrPanicSite> {
+    sites().values().cloned().collect()
+}
+
+/// Sets a callback run on each occurrence of the `log_or_panic` family of macros, receiving the
+/// updated call site.  It runs on the thread hitting the macro, before it logs or panics, so it
+/// should be quick.  To report occurrences to a dedicated appender, the callback can log them
+/// with a target which `log.toml` routes to that appender.
+pub fn set_log_or_panic_callback<F: 'static + Fn(&LogOrPanicSite) + Send + Sync>(callback: F) {
+    *unwrap!(CALLBACK.write()) = Some(Arc::new(callback));
+}
+
+/// Removes the callback set via [`set_log_or_panic_callback`](fn.set_log_or_panic_callback.html).
+pub fn clear_log_or_panic_callback() {
+    *unwrap!(CALLBACK.write()) = None;
+}
+
+/// Counts an occurrence at the given call site.
+#[doc(hidden)]
+pub fn __record_log_or_panic(
+    module: &'static str,
+    file: &'static str,
+    line: u32,
+    column: u32,
+    message: &str,
+) {
+    let site = {
+        let mut sites = sites();
+        let site = sites
+            .entry((file, line, column))
+            .or_insert_with(|| LogOrPanicSite {
+                module,
+                file,
+                line,
+                column,
+                count: 0,
+                last_message: String::new(),
+            });
+        site.count += 1;
+        site.last_message = message.to_owned();
+        site.clone()
+    };
+
+    let callback = match CALLBACK.read() {
+        Ok(callback) => callback.clone(),
+        Err(poisoned) => poisoned.into_inner().clone(),
+    };
+    if let Some(callback) = callback {
+        callback(&site);
+    }
+}
+
+// The registry is still usable after a thread panicked while holding the lock.
+fn sites() -> MutexGuard<'static, BTreeMap<SiteKey, LogOrPanicSite>> {
+    match SITES.lock() {
+        Ok(sites) => sites,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
+
+/// Makes the `log_or_panic` family of macros panic even in builds without the "testing" feature,
+/// e.g. while soak testing release builds.
+pub fn set_log_or_panic_forced(forced: bool) {
+    FORCED.store(forced, Ordering::SeqCst);
+}
+
+/// Returns whether panicking was forced via
+/// [`set_log_or_panic_forced`](fn.set_log_or_panic_forced.html).
+pub fn is_log_or_panic_forced() -> bool {
+    FORCED.load(Ordering::SeqCst)
+}
+
+/// Evaluates to whether the `log_or_panic` macros should panic in the calling crate.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __log_or_panic_panics {
+    () => {
+        (cfg!(any(test, feature = "testing")) || $crate::is_log_or_panic_forced())
+            && !::std::thread::panicking()
+    };
+}
+
+/// This macro will panic with the given message if the "testing" feature is enabled or panicking
+/// has been forced via [`set_log_or_panic_forced`](fn.set_log_or_panic_forced.html), and the
+/// calling thread is not already panicking, otherwise it will simply log an error message.
+///
+/// Either way, the occurrence is counted for [`log_or_panic_sites`](fn.log_or_panic_sites.html).
 ///
 /// # Example
 /// ```no_run
@@ -23,17 +152,121 @@
 /// ```
 #[macro_export]
 macro_rules! log_or_panic {
-    ($($arg:tt)*) => {
-        if cfg!(any(test, feature = "testing")) && !::std::thread::panicking() {
-            panic!($($arg)*);
+    ($($arg:tt)*) => {{
+        let msg = format!($($arg)*);
+        $crate::__record_log_or_panic(module_path!(), file!(), line!(), column!(), &msg);
+        $crate::__log_or_panic_report!(msg);
+    }};
+}
+
+/// Panics or logs an error with the formatted message, without counting the occurrence.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __log_or_panic_report {
+    ($msg:expr) => {
+        if $crate::__log_or_panic_panics!() {
+            panic!("{}", $msg);
         } else {
-            error!($($arg)*);
+            error!("{}", $msg);
+        }
+    };
+}
+
+/// Like `assert!`, but calls [`log_or_panic!`](macro.log_or_panic.html) if the condition is false.
+///
+/// # Example
+/// ```no_run
+/// #[macro_use]
+/// extern crate log;
+/// #[macro_use]
+/// extern crate maidsafe_utilities;
+///
+/// fn main() {
+///     let peers = 3;
+///     log_or_panic_assert!(peers > 0);
+///     log_or_panic_assert!(peers < 100, "Too many peers: {}", peers);
+/// }
+/// ```
+#[macro_export]
+macro_rules! log_or_panic_assert {
+    ($cond:expr) => {
+        $crate::log_or_panic_assert!($cond, "assertion failed: {}", stringify!($cond))
+    };
+    ($cond:expr, $($arg:tt)+) => {
+        if !$cond {
+            $crate::log_or_panic!($($arg)+);
+        }
+    };
+}
+
+/// Unwraps a `Result`, calling [`log_or_panic!`](macro.log_or_panic.html) with the error if it is
+/// an `Err` and evaluating to the given fallback, or to `Default::default()` if none is given.
+///
+/// # Example
+/// ```no_run
+/// #[macro_use]
+/// extern crate log;
+/// #[macro_use]
+/// extern crate maidsafe_utilities;
+///
+/// fn main() {
+///     let port: u16 = log_or_panic_unwrap!("5483".parse(), 5483);
+///     let count: u32 = log_or_panic_unwrap!("12".parse());
+/// }
+/// ```
+#[macro_export]
+macro_rules! log_or_panic_unwrap {
+    ($result:expr) => {
+        $crate::log_or_panic_unwrap!($result, ::std::default::Default::default())
+    };
+    ($result:expr, $fallback:expr) => {
+        match $result {
+            Ok(value) => value,
+            Err(error) => {
+                $crate::log_or_panic!("{} failed: {:?}", stringify!($result), error);
+                $fallback
+            }
         }
     };
 }
 
+/// Like [`log_or_panic!`](macro.log_or_panic.html), but only logs the first occurrence at each
+/// call site, so that it can be used on hot paths.  It still panics on every occurrence where
+/// `log_or_panic!` would, and counts every occurrence.
+///
+/// # Example
+/// ```no_run
+/// #[macro_use]
+/// extern crate log;
+/// #[macro_use]
+/// extern crate maidsafe_utilities;
+///
+/// fn main() {
+///     for i in 0..1000 {
+///         log_or_panic_once!("Bad value: {}", i);
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! log_or_panic_once {
+    ($($arg:tt)*) => {{
+        use ::std::sync::atomic::{AtomicBool, Ordering};
+        static LOGGED: AtomicBool = AtomicBool::new(false);
+        let msg = format!($($arg)*);
+        $crate::__record_log_or_panic(module_path!(), file!(), line!(), column!(), &msg);
+        if $crate::__log_or_panic_panics!() || !LOGGED.swap(true, Ordering::Relaxed) {
+            $crate::__log_or_panic_report!(msg);
+        }
+    }};
+}
+
 #[cfg(test)]
 mod tests {
+    use super::*;
+    use std::panic;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::mpsc;
+
     struct Helper;
     impl Drop for Helper {
         fn drop(&mut self) {
@@ -48,4 +281,97 @@ mod tests {
         let _helper = Helper;
         log_or_panic!("Bad value: {}", 1746);
     }
+
+    #[test]
+    fn assert_and_unwrap_pass_through() {
+        log_or_panic_assert!(1 + 1 == 2);
+        log_or_panic_assert!(true, "Not logged");
+        assert_eq!(log_or_panic_unwrap!("17".parse::<u32>()), 17);
+        assert_eq!(log_or_panic_unwrap!("17".parse::<u32>(), 0), 17);
+    }
+
+    #[test]
+    #[should_panic(expected = "assertion failed: 1 + 1 == 3")]
+    fn assert_fails() {
+        log_or_panic_assert!(1 + 1 == 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "Bad sum: 2")]
+    fn assert_fails_with_message() {
+        log_or_panic_assert!(1 + 1 == 3, "Bad sum: {}", 1 + 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "\"x\".parse::<u32>() failed: ParseIntError")]
+    fn unwrap_fails() {
+        let _: u32 = log_or_panic_unwrap!("x".parse::<u32>(), 0);
+    }
+
+    #[test]
+    fn once_panics_on_every_occurrence() {
+        for _ in 0..2 {
+            let result = panic::catch_unwind(|| log_or_panic_once!("Bad value: {}", 1746));
+            assert!(result.is_err());
+        }
+    }
+
+    #[test]
+    fn count_occurrences() {
+        let (tx, rx) = mpsc::channel();
+        let tx = Mutex::new(tx);
+        set_log_or_panic_callback(move |site| {
+            if site.last_message.starts_with("Counted") {
+                unwrap!(unwrap!(tx.lock()).send(site.clone()));
+            }
+        });
+        for i in 0..3 {
+            let _ = panic::catch_unwind(|| log_or_panic_once!("Counted {}", i));
+        }
+        clear_log_or_panic_callback();
+
+        let site = unwrap!(log_or_panic_sites()
+            .into_iter()
+            .find(|site| site.last_message.starts_with("Counted")));
+        assert_eq!(site.module, "maidsafe_utilities::log_or_panic::tests");
+        assert_eq!(site.file, file!());
+        assert_eq!(site.count, 3);
+        assert_eq!(site.last_message, "Counted 2");
+        let counts: Vec<_> = rx.try_iter().map(|site| site.count).collect();
+        assert_eq!(counts, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn evaluate_arguments_once() {
+        let evaluations = AtomicUsize::new(0);
+        let result = panic::catch_unwind(|| {
+            log_or_panic!("Evaluated {}", evaluations.fetch_add(1, Ordering::SeqCst))
+        });
+        assert!(result.is_err());
+        assert_eq!(evaluations.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn distinguish_sites_by_column() {
+        let hit = |site: fn()| panic::catch_unwind(site).is_err();
+        assert!(hit(|| log_or_panic!("Column 1")) && hit(|| log_or_panic!("Column 2")));
+
+        let sites: Vec<_> = log_or_panic_sites()
+            .into_iter()
+            .filter(|site| site.last_message.starts_with("Column"))
+            .collect();
+        assert_eq!(sites.len(), 2);
+        assert_eq!(sites[0].line, sites[1].line);
+        assert!(sites[0].column < sites[1].column);
+        assert_eq!(sites[0].last_message, "Column 1");
+    }
+
+    #[test]
+    fn force_panics() {
+        assert!(!is_log_or_panic_forced());
+        set_log_or_panic_forced(true);
+        assert!(is_log_or_panic_forced());
+        set_log_or_panic_forced(false);
+        assert!(!is_log_or_panic_forced());
+    }
 }
@@ -0,0 +1,156 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use super::SerialisationError;
+use bincode::read_types::BincodeRead;
+use bincode::{self, Deserializer, ErrorKind, Infinite};
+use serde::de::{Deserialize, Visitor};
+use std::io::{self, Read};
+use std::str;
+
+/// A bincode reader over a slice which leaves the unread remainder of the slice with the caller,
+/// unlike `bincode::read_types::SliceReader`.
+struct RemainderReader<'a: 'b, 'b> {
+    remainder: &'b mut &'a [u8],
+}
+
+impl<'a, 'b> RemainderReader<'a, 'b> {
+    fn take(&mut self, length: usize) -> bincode::Result<&'a [u8]> {
+        if length > self.remainder.len() {
+            return Err(Box::new(ErrorKind::IoError(
+                io::ErrorKind::UnexpectedEof.into(),
+            )));
+        }
+        let (taken, remainder) = self.remainder.split_at(length);
+        *self.remainder = remainder;
+        Ok(taken)
+    }
+}
+
+impl<'a, 'b> Read for RemainderReader<'a, 'b> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        self.remainder.read(out)
+    }
+}
+
+impl<'a, 'b> BincodeRead<'a> for RemainderReader<'a, 'b> {
+    fn forward_read_str<V: Visitor<'a>>(
+        &mut self,
+        length: usize,
+        visitor: V,
+    ) -> bincode::Result<V::Value> {
+        let string = str::from_utf8(self.take(length)?).map_err(|_| {
+            Box::new(ErrorKind::InvalidEncoding {
+                desc: "string was not valid utf8",
+                detail: None,
+            })
+        })?;
+        visitor.visit_borrowed_str(string)
+    }
+
+    fn get_byte_buffer(&mut self, length: usize) -> bincode::Result<Vec<u8>> {
+        self.take(length).map(<[u8]>::to_vec)
+    }
+
+    fn forward_read_bytes<V: Visitor<'a>>(
+        &mut self,
+        length: usize,
+        visitor: V,
+    ) -> bincode::Result<V::Value> {
+        visitor.visit_borrowed_bytes(self.take(length)?)
+    }
+}
+
+/// Deserialise a `Deserialize` type which may borrow `&str` and `&[u8]` fields from `data`, rather
+/// than copying them.
+///
+/// Unlike [`deserialise`](fn.deserialise.html), trailing bytes are detected from the position
+/// reached while decoding, without serialising the value again.
+pub fn deserialise_borrowed<'a, T>(data: &'a [u8]) -> Result<T, SerialisationError>
+where
+    T: Deserialize<'a>,
+{
+    let mut remainder = data;
+    let value = {
+        let reader = RemainderReader {
+            remainder: &mut remainder,
+        };
+        // Lengths are checked against the remaining data as they're read, so no limit is needed.
+        let mut deserializer = Deserializer::new(reader, Infinite);
+        T::deserialize(&mut deserializer).map_err(|e| SerialisationError::Deserialise(*e))?
+    };
+    if !remainder.is_empty() {
+        return Err(SerialisationError::DeserialiseExtraBytes);
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serialisation::serialise;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Message<'a> {
+        id: u64,
+        name: &'a str,
+        payload: &'a [u8],
+        tags: Vec<String>,
+    }
+
+    fn contains(data: &[u8], borrowed: &[u8]) -> bool {
+        let start = data.as_ptr() as usize;
+        let ptr = borrowed.as_ptr() as usize;
+        ptr >= start && ptr < start + data.len()
+    }
+
+    #[test]
+    fn borrow() {
+        let payload = [1u8, 2, 3];
+        let original = Message {
+            id: 7,
+            name: "SomeString",
+            payload: &payload,
+            tags: vec!["a".to_owned()],
+        };
+        let serialised = unwrap!(serialise(&original));
+        let deserialised: Message = unwrap!(deserialise_borrowed(&serialised));
+        assert_eq!(deserialised, original);
+        assert!(contains(&serialised, deserialised.name.as_bytes()));
+        assert!(contains(&serialised, deserialised.payload));
+    }
+
+    #[test]
+    fn invalid_data() {
+        let mut serialised = unwrap!(serialise(&"SomeString"));
+        serialised.push(0);
+        match unwrap_err!(deserialise_borrowed::<&str>(&serialised)) {
+            SerialisationError::DeserialiseExtraBytes => (),
+            err => panic!("{:?}", err),
+        }
+
+        let truncated = &serialised[..serialised.len() - 2];
+        match unwrap_err!(deserialise_borrowed::<&str>(truncated)) {
+            SerialisationError::Deserialise(ErrorKind::IoError(_)) => (),
+            err => panic!("{:?}", err),
+        }
+
+        // An excessive length prefix fails without allocating.
+        match unwrap_err!(deserialise_borrowed::<Vec<u8>>(&[255u8; 9])) {
+            SerialisationError::Deserialise(_) => (),
+            err => panic!("{:?}", err),
+        }
+
+        let invalid_utf8 = [1u8, 0, 0, 0, 0, 0, 0, 0, 0xff];
+        match unwrap_err!(deserialise_borrowed::<&str>(&invalid_utf8)) {
+            SerialisationError::Deserialise(ErrorKind::InvalidEncoding { .. }) => (),
+            err => panic!("{:?}", err),
+        }
+    }
+}
@@ -0,0 +1,243 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use super::{deserialise, serialise, SerialisationError};
+use bincode::{deserialize_from, Bounded, ErrorKind};
+use serde::de::DeserializeOwned;
+use serde::ser::Serialize;
+use std::io::{self, Read, Write};
+
+/// Size of the checksum appended to the serialised data.
+const CHECKSUM_LEN: usize = 4;
+
+/// Reversed polynomial of the CRC-32 used by Ethernet, zlib and PNG.
+const POLYNOMIAL: u32 = 0xEDB8_8320;
+
+lazy_static! {
+    static ref TABLE: [u32; 256] = {
+        let mut table = [0u32; 256];
+        for (index, entry) in table.iter_mut().enumerate() {
+            let mut crc = index as u32;
+            for _ in 0..8 {
+                crc = if crc & 1 == 1 {
+                    (crc >> 1) ^ POLYNOMIAL
+                } else {
+                    crc >> 1
+                };
+            }
+            *entry = crc;
+        }
+        table
+    };
+}
+
+/// Incremental CRC-32 computation.
+struct Crc32(u32);
+
+impl Crc32 {
+    fn new() -> Self {
+        Crc32(!0)
+    }
+
+    fn update(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = TABLE[((self.0 ^ u32::from(byte)) & 0xff) as usize] ^ (self.0 >> 8);
+        }
+    }
+
+    fn finish(&self) -> [u8; CHECKSUM_LEN] {
+        let crc = !self.0;
+        [
+            crc as u8,
+            (crc >> 8) as u8,
+            (crc >> 16) as u8,
+            (crc >> 24) as u8,
+        ]
+    }
+}
+
+fn checksum(bytes: &[u8]) -> [u8; CHECKSUM_LEN] {
+    let mut crc = Crc32::new();
+    crc.update(bytes);
+    crc.finish()
+}
+
+/// Passes reads through, adding the bytes read to a checksum.
+struct ChecksumReader<'a, R: 'a> {
+    read: &'a mut R,
+    crc: Crc32,
+}
+
+impl<'a, R: Read> Read for ChecksumReader<'a, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.read.read(buf)?;
+        self.crc.update(&buf[..len]);
+        Ok(len)
+    }
+}
+
+/// Serialise an `Serialize` type with no limit on the size of the serialised data, followed by a
+/// CRC-32 checksum of the serialised data.
+pub fn serialise_with_checksum<T: Serialize>(data: &T) -> Result<Vec<u8>, SerialisationError> {
+    let mut serialised = serialise(data)?;
+    let checksum = checksum(&serialised);
+    serialised.extend_from_slice(&checksum);
+    Ok(serialised)
+}
+
+/// Deserialise a `Deserialize` type serialised via
+/// [`serialise_with_checksum`](fn.serialise_with_checksum.html), failing with
+/// `SerialisationError::ChecksumMismatch` if the data has been corrupted.
+pub fn deserialise_with_checksum<T>(data: &[u8]) -> Result<T, SerialisationError>
+where
+    T: Serialize + DeserializeOwned,
+{
+    if data.len() < CHECKSUM_LEN {
+        return Err(SerialisationError::ChecksumMismatch);
+    }
+    let (payload, expected) = data.split_at(data.len() - CHECKSUM_LEN);
+    if checksum(payload) != expected {
+        return Err(SerialisationError::ChecksumMismatch);
+    }
+    deserialise(payload)
+}
+
+/// Serialise an `Serialize` type directly into a `Write` with no limit on the size of the
+/// serialised data, followed by a CRC-32 checksum of the serialised data.
+pub fn serialise_into_with_checksum<T: Serialize, W: Write>(
+    data: &T,
+    write: &mut W,
+) -> Result<(), SerialisationError> {
+    let serialised = serialise_with_checksum(data)?;
+    write
+        .write_all(&serialised)
+        .map_err(|e| SerialisationError::Serialise(ErrorKind::IoError(e)))
+}
+
+/// Deserialise a `Deserialize` type directly from a `Read`, as written by
+/// [`serialise_into_with_checksum`](fn.serialise_into_with_checksum.html), with max size limit
+/// specified for the serialised data excluding the checksum.
+///
+/// The checksum follows the data, so it can only be verified once the data has been decoded.  Data
+/// which fails to decode is reported as `SerialisationError::Deserialise`, whether or not it has
+/// been corrupted.
+pub fn deserialise_from_with_checksum<R: Read, T: DeserializeOwned>(
+    read: &mut R,
+    size_limit: Bounded,
+) -> Result<T, SerialisationError> {
+    let mut reader = ChecksumReader {
+        read,
+        crc: Crc32::new(),
+    };
+    let value = deserialize_from(&mut reader, size_limit)
+        .map_err(|e| SerialisationError::Deserialise(*e))?;
+    let mut expected = [0; CHECKSUM_LEN];
+    reader
+        .read
+        .read_exact(&mut expected)
+        .map_err(|e| SerialisationError::Deserialise(ErrorKind::IoError(e)))?;
+    if reader.crc.finish() != expected {
+        return Err(SerialisationError::ChecksumMismatch);
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn crc32() {
+        // The standard check value of CRC-32.
+        assert_eq!(checksum(b"123456789"), [0x26, 0x39, 0xf4, 0xcb]);
+        assert_eq!(checksum(b""), [0; 4]);
+    }
+
+    #[test]
+    fn slice() {
+        let original_data = (vec![0u8, 1, 3, 9], "SomeString".to_string());
+        let mut serialised = unwrap!(serialise_with_checksum(&original_data));
+        assert_eq!(
+            serialised.len(),
+            unwrap!(serialise(&original_data)).len() + CHECKSUM_LEN
+        );
+        let deserialised: (Vec<u8>, String) = unwrap!(deserialise_with_checksum(&serialised));
+        assert_eq!(deserialised, original_data);
+
+        // Flip a bit which still decodes, changing the first element of the vector.
+        serialised[8] ^= 1;
+        match unwrap_err!(deserialise_with_checksum::<(Vec<u8>, String)>(&serialised)) {
+            SerialisationError::ChecksumMismatch => (),
+            err => panic!("{:?}", err),
+        }
+        match unwrap_err!(deserialise_with_checksum::<u8>(&[1, 2])) {
+            SerialisationError::ChecksumMismatch => (),
+            err => panic!("{:?}", err),
+        }
+    }
+
+    #[test]
+    fn stream() {
+        let mut serialised = Vec::new();
+        unwrap!(serialise_into_with_checksum(&7u64, &mut serialised));
+        unwrap!(serialise_into_with_checksum(
+            &"SomeString".to_string(),
+            &mut serialised
+        ));
+
+        let limit = Bounded(64);
+        let mut read = Cursor::new(serialised.clone());
+        assert_eq!(
+            unwrap!(deserialise_from_with_checksum::<_, u64>(&mut read, limit)),
+            7
+        );
+        assert_eq!(
+            unwrap!(deserialise_from_with_checksum::<_, String>(
+                &mut read, limit
+            )),
+            "SomeString"
+        );
+
+        // Data exceeding the limit isn't decoded.
+        let mut read = Cursor::new(&serialised[12..]);
+        match unwrap_err!(deserialise_from_with_checksum::<_, String>(
+            &mut read,
+            Bounded(8)
+        )) {
+            SerialisationError::Deserialise(ErrorKind::SizeLimit) => (),
+            err => panic!("{:?}", err),
+        }
+
+        // Data which fails to decode is reported as such.
+        let mut invalid_utf8 = serialised[12..].to_vec();
+        invalid_utf8[8] = 0xff;
+        let mut read = Cursor::new(invalid_utf8);
+        match unwrap_err!(deserialise_from_with_checksum::<_, String>(
+            &mut read, limit
+        )) {
+            SerialisationError::Deserialise(ErrorKind::InvalidEncoding { .. }) => (),
+            err => panic!("{:?}", err),
+        }
+
+        serialised[0] ^= 1;
+        let mut read = Cursor::new(serialised.clone());
+        match unwrap_err!(deserialise_from_with_checksum::<_, u64>(&mut read, limit)) {
+            SerialisationError::ChecksumMismatch => (),
+            err => panic!("{:?}", err),
+        }
+
+        // A truncated stream is reported as an I/O error.
+        let mut read = Cursor::new(&serialised[..10]);
+        match unwrap_err!(deserialise_from_with_checksum::<_, u64>(&mut read, limit)) {
+            SerialisationError::Deserialise(ErrorKind::IoError(_)) => (),
+            err => panic!("{:?}", err),
+        }
+    }
+}
@@ -0,0 +1,232 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use super::{deserialise_with_limit, serialise_with_limit, SerialisationError};
+use bincode::{Bounded, ErrorKind};
+use serde::de::DeserializeOwned;
+use serde::ser::Serialize;
+use std::io::{self, Read, Write};
+use std::marker::PhantomData;
+
+/// Size of the little-endian length prefix of each frame.
+const PREFIX_LEN: usize = 4;
+
+fn write_error(error: io::Error) -> SerialisationError {
+    SerialisationError::Serialise(ErrorKind::IoError(error))
+}
+
+fn read_error(error: io::Error) -> SerialisationError {
+    SerialisationError::Deserialise(ErrorKind::IoError(error))
+}
+
+/// Writes serialised messages to a stream, each prefixed by its length, for reading via
+/// [`FramedReader`](struct.FramedReader.html).
+#[derive(Debug)]
+pub struct FramedWriter<W> {
+    write: W,
+    max_frame_size: u64,
+}
+
+impl<W: Write> FramedWriter<W> {
+    /// Creates a writer which rejects messages serialising to more than `max_frame_size` bytes.
+    pub fn new(write: W, max_frame_size: Bounded) -> Self {
+        FramedWriter {
+            write,
+            max_frame_size: max_frame_size.0,
+        }
+    }
+
+    /// Writes one message as a single frame.  Messages above the maximum frame size fail with
+    /// `ErrorKind::SizeLimit` without anything being written.
+    pub fn write<T: Serialize>(&mut self, message: &T) -> Result<(), SerialisationError> {
+        let payload = serialise_with_limit(message, Bounded(self.max_frame_size))?;
+        if payload.len() > std::u32::MAX as usize {
+            return Err(SerialisationError::Serialise(ErrorKind::SizeLimit));
+        }
+        let len = payload.len() as u32;
+        let prefix = [
+            len as u8,
+            (len >> 8) as u8,
+            (len >> 16) as u8,
+            (len >> 24) as u8,
+        ];
+        self.write.write_all(&prefix).map_err(write_error)?;
+        self.write.write_all(&payload).map_err(write_error)
+    }
+
+    /// Flushes the underlying stream.
+    pub fn flush(&mut self) -> Result<(), SerialisationError> {
+        self.write.flush().map_err(write_error)
+    }
+
+    /// Returns a reference to the underlying stream.
+    pub fn get_ref(&self) -> &W {
+        &self.write
+    }
+
+    /// Returns the underlying stream.
+    pub fn into_inner(self) -> W {
+        self.write
+    }
+}
+
+/// Reads messages of type `T` written by a [`FramedWriter`](struct.FramedWriter.html) from a
+/// stream.
+///
+/// A frame above the maximum size is skipped, and one which fails to decode is consumed entirely,
+/// so that reading can carry on with the next frame after such an error.  Iterating yields each
+/// message or error in turn, ending when the stream ends between two frames.
+#[derive(Debug)]
+pub struct FramedReader<R, T> {
+    read: R,
+    max_frame_size: u64,
+    _message: PhantomData<fn() -> T>,
+}
+
+impl<R: Read, T: DeserializeOwned> FramedReader<R, T> {
+    /// Creates a reader which rejects frames of more than `max_frame_size` bytes.
+    pub fn new(read: R, max_frame_size: Bounded) -> Self {
+        FramedReader {
+            read,
+            max_frame_size: max_frame_size.0,
+            _message: PhantomData,
+        }
+    }
+
+    /// Reads the next message, returning `None` if the stream ended before another frame.
+    pub fn read(&mut self) -> Result<Option<T>, SerialisationError> {
+        let len = match self.read_prefix()? {
+            Some(len) => len,
+            None => return Ok(None),
+        };
+
+        if len > self.max_frame_size {
+            let skipped =
+                io::copy(&mut (&mut self.read).take(len), &mut io::sink()).map_err(read_error)?;
+            if skipped < len {
+                return Err(read_error(io::ErrorKind::UnexpectedEof.into()));
+            }
+            return Err(SerialisationError::Deserialise(ErrorKind::SizeLimit));
+        }
+
+        let mut payload = vec![0; len as usize];
+        self.read.read_exact(&mut payload).map_err(read_error)?;
+        deserialise_with_limit(&payload, Bounded(self.max_frame_size)).map(Some)
+    }
+
+    /// Returns a reference to the underlying stream.
+    pub fn get_ref(&self) -> &R {
+        &self.read
+    }
+
+    /// Returns the underlying stream.
+    pub fn into_inner(self) -> R {
+        self.read
+    }
+
+    fn read_prefix(&mut self) -> Result<Option<u64>, SerialisationError> {
+        let mut prefix = [0u8; PREFIX_LEN];
+        let mut filled = 0;
+        while filled < PREFIX_LEN {
+            match self.read.read(&mut prefix[filled..]) {
+                Ok(0) if filled == 0 => return Ok(None),
+                Ok(0) => return Err(read_error(io::ErrorKind::UnexpectedEof.into())),
+                Ok(count) => filled += count,
+                Err(ref error) if error.kind() == io::ErrorKind::Interrupted => (),
+                Err(error) => return Err(read_error(error)),
+            }
+        }
+        Ok(Some(
+            prefix
+                .iter()
+                .rev()
+                .fold(0, |len, &byte| (len << 8) | u64::from(byte)),
+        ))
+    }
+}
+
+impl<R: Read, T: DeserializeOwned> Iterator for FramedReader<R, T> {
+    type Item = Result<T, SerialisationError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.read() {
+            Ok(Some(message)) => Some(Ok(message)),
+            Ok(None) => None,
+            Err(error) => Some(Err(error)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serialisation::serialise;
+    use std::io::Cursor;
+
+    #[test]
+    fn round_trip() {
+        let mut writer = FramedWriter::new(Vec::new(), Bounded(64));
+        unwrap!(writer.write(&"First".to_owned()));
+        unwrap!(writer.write(&"Second".to_owned()));
+        unwrap!(writer.flush());
+        let written = writer.into_inner();
+        assert_eq!(&written[..PREFIX_LEN], &[13, 0, 0, 0]);
+
+        let reader = FramedReader::<_, String>::new(Cursor::new(written), Bounded(64));
+        let messages: Vec<_> = reader.map(|message| unwrap!(message)).collect();
+        assert_eq!(messages, vec!["First".to_owned(), "Second".to_owned()]);
+    }
+
+    #[test]
+    fn oversized_frames() {
+        let mut writer = FramedWriter::new(Vec::new(), Bounded(16));
+        match unwrap_err!(writer.write(&vec![0u64; 2])) {
+            SerialisationError::Serialise(ErrorKind::SizeLimit) => (),
+            err => panic!("{:?}", err),
+        }
+        assert!(writer.get_ref().is_empty());
+
+        // A reader with a lower limit skips the oversized frame.
+        unwrap!(writer.write(&vec![7u8; 4]));
+        unwrap!(writer.write(&vec![8u8; 1]));
+        let mut reader =
+            FramedReader::<_, Vec<u8>>::new(Cursor::new(writer.into_inner()), Bounded(10));
+        match unwrap_err!(unwrap!(reader.next())) {
+            SerialisationError::Deserialise(ErrorKind::SizeLimit) => (),
+            err => panic!("{:?}", err),
+        }
+        assert_eq!(unwrap!(unwrap!(reader.next())), vec![8u8]);
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn invalid_frames() {
+        let mut written = Vec::new();
+        // A frame holding a `u64` and an extra byte, followed by a valid one.
+        written.extend_from_slice(&[9, 0, 0, 0]);
+        written.extend_from_slice(&unwrap!(serialise(&1u64)));
+        written.push(0);
+        written.extend_from_slice(&[8, 0, 0, 0]);
+        written.extend_from_slice(&unwrap!(serialise(&2u64)));
+        // A truncated frame.
+        written.extend_from_slice(&[8, 0, 0, 0, 1]);
+
+        let mut reader = FramedReader::<_, u64>::new(Cursor::new(written), Bounded(64));
+        match unwrap_err!(unwrap!(reader.next())) {
+            SerialisationError::DeserialiseExtraBytes => (),
+            err => panic!("{:?}", err),
+        }
+        assert_eq!(unwrap!(unwrap!(reader.next())), 2);
+        match unwrap_err!(unwrap!(reader.next())) {
+            SerialisationError::Deserialise(ErrorKind::IoError(_)) => (),
+            err => panic!("{:?}", err),
+        }
+        assert!(reader.next().is_none());
+    }
+}
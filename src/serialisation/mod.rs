@@ -15,6 +15,19 @@ use serde::de::DeserializeOwned;
 use serde::ser::Serialize;
 use std::io::{Cursor, Read, Write};
 
+pub use self::borrowed::deserialise_borrowed;
+pub use self::checksum::{
+    deserialise_from_with_checksum, deserialise_with_checksum, serialise_into_with_checksum,
+    serialise_with_checksum,
+};
+pub use self::framed::{FramedReader, FramedWriter};
+pub use self::versioned::{deserialise_versioned, serialise_versioned, Migrations, Versioned};
+
+mod borrowed;
+mod checksum;
+mod framed;
+mod versioned;
+
 quick_error! {
     /// Serialisation error.
     #[derive(Debug)]
@@ -38,6 +51,32 @@ quick_error! {
             description("DeserialiseExtraBytes error")
             display("Deserialise error: Not all bytes of slice consumed")
         }
+
+        /// The checksum of the data doesn't match the one stored with it, so it has been corrupted.
+        ChecksumMismatch {
+            description("ChecksumMismatch error")
+            display("Deserialise error: Checksum mismatch")
+        }
+
+        /// The data doesn't start with a valid envelope of a versioned type.
+        InvalidEnvelope {
+            description("InvalidEnvelope error")
+            display("Deserialise error: Data doesn't start with a valid versioned envelope")
+        }
+
+        /// The envelope of a versioned type holds another type.  Holds the expected and found type
+        /// tags.
+        TypeMismatch(expected: String, found: String) {
+            description("TypeMismatch error")
+            display("Deserialise error: Expected type '{}', found '{}'", expected, found)
+        }
+
+        /// The envelope of a versioned type holds a version which is neither the current one nor
+        /// one with a registered migration.  Holds the expected and found versions.
+        VersionMismatch(expected: u32, found: u32) {
+            description("VersionMismatch error")
+            display("Deserialise error: Expected version {}, found {}", expected, found)
+        }
     }
 }
 
@@ -0,0 +1,261 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use super::{deserialise, serialise, SerialisationError};
+use bincode::{deserialize_from, Bounded};
+use serde::de::DeserializeOwned;
+use serde::ser::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::{self, Debug, Formatter};
+use std::io::Cursor;
+
+/// Bytes at the start of every envelope.
+const MAGIC: [u8; 4] = *b"MSVE";
+
+/// Version of the envelope layout itself.
+const ENVELOPE_FORMAT: u8 = 1;
+
+/// A type which can be serialised in a versioned envelope via
+/// [`serialise_versioned`](fn.serialise_versioned.html).
+pub trait Versioned: Serialize + DeserializeOwned {
+    /// Identifies the type, so that a message of one type isn't decoded as another.
+    const TYPE_TAG: &'static str;
+    /// The current version of the type's encoding, to be incremented whenever it changes.
+    const VERSION: u32;
+}
+
+/// Serialise a `Versioned` type, prefixed by an envelope holding magic bytes, the type tag and the
+/// version.
+pub fn serialise_versioned<T: Versioned>(data: &T) -> Result<Vec<u8>, SerialisationError> {
+    let mut serialised = serialise(&(MAGIC, ENVELOPE_FORMAT, T::TYPE_TAG, T::VERSION))?;
+    serialised.extend_from_slice(&serialise(data)?);
+    Ok(serialised)
+}
+
+/// Deserialise a `Versioned` type serialised via
+/// [`serialise_versioned`](fn.serialise_versioned.html), failing with
+/// `SerialisationError::VersionMismatch` if it was serialised with another version.  Use
+/// [`Migrations`](struct.Migrations.html) to accept older versions.
+pub fn deserialise_versioned<T: Versioned>(data: &[u8]) -> Result<T, SerialisationError> {
+    Migrations::new().deserialise(data)
+}
+
+type Migration<T> = Box<Fn(&[u8]) -> Result<T, SerialisationError> + Send + Sync>;
+
+/// A registry of migrations from older versions of a `Versioned` type.
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate serde_derive;
+/// # #[macro_use]
+/// # extern crate unwrap;
+/// # extern crate maidsafe_utilities;
+/// use maidsafe_utilities::serialisation::{self, Migrations, Versioned};
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct PeerV1 {
+///     name: u64,
+/// }
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Peer {
+///     name: u64,
+///     port: u16,
+/// }
+///
+/// impl Versioned for Peer {
+///     const TYPE_TAG: &'static str = "Peer";
+///     const VERSION: u32 = 2;
+/// }
+///
+/// # fn main() {
+/// let migrations = Migrations::new().migrate_from(1, |old: PeerV1| Peer {
+///     name: old.name,
+///     port: 5483,
+/// });
+/// # let serialised = unwrap!(serialisation::serialise_versioned(&Peer { name: 1, port: 2 }));
+/// let peer: Peer = unwrap!(migrations.deserialise(&serialised));
+/// # }
+/// ```
+pub struct Migrations<T> {
+    migrations: BTreeMap<u32, Migration<T>>,
+}
+
+impl<T: Versioned> Migrations<T> {
+    /// Creates a registry without migrations, i.e. accepting the current version only.
+    pub fn new() -> Self {
+        Migrations {
+            migrations: BTreeMap::new(),
+        }
+    }
+
+    /// Registers a migration decoding the payload of `version` and converting it to `T`.
+    pub fn register<F>(mut self, version: u32, migration: F) -> Self
+    where
+        F: 'static + Fn(&[u8]) -> Result<T, SerialisationError> + Send + Sync,
+    {
+        let _ = self.migrations.insert(version, Box::new(migration));
+        self
+    }
+
+    /// Registers a migration from `version`, which was encoded as `Old`.
+    pub fn migrate_from<Old, F>(self, version: u32, convert: F) -> Self
+    where
+        Old: Serialize + DeserializeOwned,
+        F: 'static + Fn(Old) -> T + Send + Sync,
+    {
+        self.register(version, move |payload| {
+            deserialise::<Old>(payload).map(&convert)
+        })
+    }
+
+    /// Deserialise `T` from an envelope holding either the current version or one with a
+    /// registered migration.
+    pub fn deserialise(&self, data: &[u8]) -> Result<T, SerialisationError> {
+        let mut cursor = Cursor::new(data);
+        let (magic, format, type_tag, version): ([u8; 4], u8, String, u32) =
+            deserialize_from(&mut cursor, Bounded(data.len() as u64))
+                .map_err(|_| SerialisationError::InvalidEnvelope)?;
+        if magic != MAGIC || format != ENVELOPE_FORMAT {
+            return Err(SerialisationError::InvalidEnvelope);
+        }
+        if type_tag != T::TYPE_TAG {
+            return Err(SerialisationError::TypeMismatch(
+                T::TYPE_TAG.to_owned(),
+                type_tag,
+            ));
+        }
+
+        let payload = &data[cursor.position() as usize..];
+        if version == T::VERSION {
+            return deserialise(payload);
+        }
+        match self.migrations.get(&version) {
+            Some(migration) => migration(payload),
+            None => Err(SerialisationError::VersionMismatch(T::VERSION, version)),
+        }
+    }
+}
+
+impl<T: Versioned> Default for Migrations<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Debug for Migrations<T> {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        formatter
+            .debug_struct("Migrations")
+            .field("versions", &self.migrations.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct PeerV1 {
+        name: u64,
+    }
+
+    impl Versioned for PeerV1 {
+        const TYPE_TAG: &'static str = "Peer";
+        const VERSION: u32 = 1;
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Peer {
+        name: u64,
+        port: u16,
+    }
+
+    impl Versioned for Peer {
+        const TYPE_TAG: &'static str = "Peer";
+        const VERSION: u32 = 2;
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Other(u64);
+
+    impl Versioned for Other {
+        const TYPE_TAG: &'static str = "Other";
+        const VERSION: u32 = 2;
+    }
+
+    #[test]
+    fn round_trip() {
+        let peer = Peer {
+            name: 7,
+            port: 5483,
+        };
+        let serialised = unwrap!(serialise_versioned(&peer));
+        assert!(serialised.starts_with(b"MSVE\x01"));
+        assert_eq!(unwrap!(deserialise_versioned::<Peer>(&serialised)), peer);
+    }
+
+    #[test]
+    fn mismatches() {
+        let old = unwrap!(serialise_versioned(&PeerV1 { name: 7 }));
+        match unwrap_err!(deserialise_versioned::<Peer>(&old)) {
+            SerialisationError::VersionMismatch(2, 1) => (),
+            err => panic!("{:?}", err),
+        }
+
+        let other = unwrap!(serialise_versioned(&Other(7)));
+        match unwrap_err!(deserialise_versioned::<Peer>(&other)) {
+            SerialisationError::TypeMismatch(expected, found) => {
+                assert_eq!(expected, "Peer");
+                assert_eq!(found, "Other");
+            }
+            err => panic!("{:?}", err),
+        }
+
+        // Unversioned data and truncated envelopes are rejected.
+        let unversioned = unwrap!(serialise(&Peer { name: 7, port: 1 }));
+        for data in &[&unversioned[..], &old[..3], &[][..]] {
+            match unwrap_err!(deserialise_versioned::<Peer>(data)) {
+                SerialisationError::InvalidEnvelope => (),
+                err => panic!("{:?}", err),
+            }
+        }
+
+        // Trailing bytes are detected as for unversioned data.
+        let mut extended = unwrap!(serialise_versioned(&Other(7)));
+        extended.push(0);
+        match unwrap_err!(deserialise_versioned::<Other>(&extended)) {
+            SerialisationError::DeserialiseExtraBytes => (),
+            err => panic!("{:?}", err),
+        }
+    }
+
+    #[test]
+    fn migrate() {
+        let migrations = Migrations::new().migrate_from(1, |old: PeerV1| Peer {
+            name: old.name,
+            port: 5483,
+        });
+
+        let old = unwrap!(serialise_versioned(&PeerV1 { name: 7 }));
+        assert_eq!(
+            unwrap!(migrations.deserialise(&old)),
+            Peer {
+                name: 7,
+                port: 5483
+            }
+        );
+        let current = unwrap!(serialise_versioned(&Peer { name: 8, port: 1 }));
+        assert_eq!(
+            unwrap!(migrations.deserialise(&current)),
+            Peer { name: 8, port: 1 }
+        );
+    }
+}
@@ -0,0 +1,136 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+#![doc(
+    html_logo_url = "https://raw.githubusercontent.com/maidsafe/QA/master/Images/maidsafe_logo.png",
+    html_favicon_url = "https://maidsafe.net/img/favicon.ico",
+    test(attr(forbid(warnings)))
+)]
+// For explanation of lint checks, run `rustc -W help` or see
+// https://github.com/maidsafe/QA/blob/master/Documentation/Rust%20Lint%20Checks.md
+#![forbid(
+    exceeding_bitshifts,
+    mutable_transmutes,
+    no_mangle_const_items,
+    unknown_crate_types,
+    warnings
+)]
+#![deny(
+    bad_style,
+    deprecated,
+    improper_ctypes,
+    missing_docs,
+    non_shorthand_field_patterns,
+    overflowing_literals,
+    plugin_as_library,
+    stable_features,
+    unconditional_recursion,
+    unknown_lints,
+    unsafe_code,
+    unused,
+    unused_allocation,
+    unused_attributes,
+    unused_comparisons,
+    unused_features,
+    unused_parens,
+    while_true
+)]
+#![warn(
+    trivial_casts,
+    trivial_numeric_casts,
+    unused_extern_crates,
+    unused_import_braces,
+    unused_qualifications,
+    unused_results
+)]
+#![allow(
+    box_pointers,
+    missing_copy_implementations,
+    missing_debug_implementations,
+    variant_size_differences
+)]
+
+#[macro_use]
+extern crate log as logger;
+#[macro_use]
+extern crate unwrap;
+
+mod common;
+
+use crate::common::ChannelWriter;
+use log4rs::config::{Appender, Config, Root};
+use logger::LogLevelFilter;
+use maidsafe_utilities::log::{self, AsyncWriterAppender, Context, JsonEncoder, PatternEncoder};
+use maidsafe_utilities::thread;
+use serde_json::Value;
+use std::sync::mpsc;
+use std::time::Duration;
+
+#[test]
+fn context_logging() {
+    let (text_tx, text_rx) = mpsc::channel();
+    let (json_tx, json_rx) = mpsc::channel();
+
+    let text = AsyncWriterAppender::builder(ChannelWriter(text_tx))
+        .encoder(Box::new(PatternEncoder::new("{T} [{ctx}] {m}{n}")))
+        .build();
+    let json = AsyncWriterAppender::builder(ChannelWriter(json_tx))
+        .encoder(Box::new(JsonEncoder::new()))
+        .build();
+    let config = unwrap!(Config::builder()
+        .appender(Appender::builder().build("text".to_owned(), Box::new(text)))
+        .appender(Appender::builder().build("json".to_owned(), Box::new(json)))
+        .build(
+            Root::builder()
+                .appender("text".to_owned())
+                .appender("json".to_owned())
+                .build(LogLevelFilter::Info),
+        ));
+    let _ = unwrap!(log4rs::init_config(config));
+
+    let worker = log::with_context("req_id", 42, || {
+        info!("Received request");
+        let context = Context::capture();
+        thread::named("Worker", move || {
+            context.enter(|| info!("Handling request"));
+            info!("Idle");
+        })
+    });
+    drop(worker);
+
+    let timeout = Duration::from_secs(5);
+    let texts: Vec<_> = (0..3)
+        .map(|_| unwrap!(text_rx.recv_timeout(timeout)))
+        .collect();
+    assert_eq!(
+        texts,
+        vec![
+            "context_logging [req_id=42] Received request\n".to_owned(),
+            "Worker [req_id=42] Handling request\n".to_owned(),
+            "Worker [] Idle\n".to_owned(),
+        ]
+    );
+
+    let mdcs: Vec<_> = (0..3)
+        .map(|_| {
+            let record: Value = unwrap!(serde_json::from_str(&unwrap!(
+                json_rx.recv_timeout(timeout)
+            )));
+            record["mdc"].clone()
+        })
+        .collect();
+    assert_eq!(
+        mdcs,
+        vec![
+            serde_json::json!({ "req_id": "42" }),
+            serde_json::json!({ "req_id": "42" }),
+            serde_json::json!({}),
+        ]
+    );
+}
@@ -0,0 +1,101 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+#![doc(
+    html_logo_url = "https://raw.githubusercontent.com/maidsafe/QA/master/Images/maidsafe_logo.png",
+    html_favicon_url = "https://maidsafe.net/img/favicon.ico",
+    test(attr(forbid(warnings)))
+)]
+// For explanation of lint checks, run `rustc -W help` or see
+// https://github.com/maidsafe/QA/blob/master/Documentation/Rust%20Lint%20Checks.md
+#![forbid(
+    exceeding_bitshifts,
+    mutable_transmutes,
+    no_mangle_const_items,
+    unknown_crate_types,
+    warnings
+)]
+#![deny(
+    bad_style,
+    deprecated,
+    improper_ctypes,
+    missing_docs,
+    non_shorthand_field_patterns,
+    overflowing_literals,
+    plugin_as_library,
+    stable_features,
+    unconditional_recursion,
+    unknown_lints,
+    unsafe_code,
+    unused,
+    unused_allocation,
+    unused_attributes,
+    unused_comparisons,
+    unused_features,
+    unused_parens,
+    while_true
+)]
+#![warn(
+    trivial_casts,
+    trivial_numeric_casts,
+    unused_extern_crates,
+    unused_import_braces,
+    unused_qualifications,
+    unused_results
+)]
+#![allow(
+    box_pointers,
+    missing_copy_implementations,
+    missing_debug_implementations,
+    variant_size_differences
+)]
+
+#[macro_use]
+extern crate log as logger;
+#[macro_use]
+extern crate unwrap;
+
+use maidsafe_utilities::log::{self, Encoding};
+use std::env;
+use std::fs;
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn json_file_logging() {
+    let log_file_path = env::temp_dir().join("maidsafe_utilities-json_file_logging.log");
+
+    unwrap!(log::init_to_file_with_encoding(
+        true,
+        &log_file_path,
+        false,
+        Encoding::Json,
+    ));
+
+    error!("This is message 0");
+    warn!("This is message 1");
+
+    // Wait for async file writer
+    thread::sleep(Duration::from_millis(500));
+
+    let contents = unwrap!(fs::read_to_string(&log_file_path));
+    let lines: Vec<_> = contents.lines().collect();
+    assert_eq!(lines.len(), 2, "{}", contents);
+    for (index, line) in lines.iter().enumerate() {
+        assert!(line.starts_with('{') && line.ends_with('}'), "{}", line);
+        assert!(line.contains(&format!("\"message\":\"This is message {}\"", index)));
+        assert!(line.contains("\"module_path\":\"json_file_logging\""));
+        assert!(line.contains("\"file\":\"tests/json_file_logging.rs\""));
+        assert!(line.contains("\"thread\":\"json_file_logging\""));
+    }
+    assert!(lines[0].contains("\"level\":\"ERROR\""));
+    assert!(lines[1].contains("\"level\":\"WARN\""));
+
+    unwrap!(fs::remove_file(log_file_path));
+}
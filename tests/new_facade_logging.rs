@@ -0,0 +1,133 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+#![doc(
+    html_logo_url = "https://raw.githubusercontent.com/maidsafe/QA/master/Images/maidsafe_logo.png",
+    html_favicon_url = "https://maidsafe.net/img/favicon.ico",
+    test(attr(forbid(warnings)))
+)]
+// For explanation of lint checks, run `rustc -W help` or see
+// https://github.com/maidsafe/QA/blob/master/Documentation/Rust%20Lint%20Checks.md
+#![forbid(
+    exceeding_bitshifts,
+    mutable_transmutes,
+    no_mangle_const_items,
+    unknown_crate_types,
+    warnings
+)]
+#![deny(
+    bad_style,
+    deprecated,
+    improper_ctypes,
+    missing_docs,
+    non_shorthand_field_patterns,
+    overflowing_literals,
+    plugin_as_library,
+    stable_features,
+    unconditional_recursion,
+    unknown_lints,
+    unsafe_code,
+    unused,
+    unused_allocation,
+    unused_attributes,
+    unused_comparisons,
+    unused_features,
+    unused_parens,
+    while_true
+)]
+#![warn(
+    trivial_casts,
+    trivial_numeric_casts,
+    unused_extern_crates,
+    unused_import_braces,
+    unused_qualifications,
+    unused_results
+)]
+#![allow(
+    box_pointers,
+    missing_copy_implementations,
+    missing_debug_implementations,
+    variant_size_differences
+)]
+
+#[macro_use]
+extern crate log as logger;
+#[macro_use]
+extern crate unwrap;
+
+mod common;
+
+use crate::common::ChannelWriter;
+use log4rs::config::{Appender, Config, Logger, Root};
+use logger::LogLevelFilter;
+use maidsafe_utilities::log::{AsyncWriterAppender, JsonEncoder, PatternEncoder};
+use serde_json::Value;
+use std::sync::mpsc;
+use std::time::Duration;
+
+#[test]
+fn new_facade_logging() {
+    let (tx, rx) = mpsc::channel();
+    let (json_tx, json_rx) = mpsc::channel();
+
+    let appender = AsyncWriterAppender::builder(ChannelWriter(tx))
+        .encoder(Box::new(PatternEncoder::new("{l} [{M} {fn}] {m}{n}")))
+        .build();
+    let json = AsyncWriterAppender::builder(ChannelWriter(json_tx))
+        .encoder(Box::new(JsonEncoder::new()))
+        .build();
+    let config = unwrap!(Config::builder()
+        .appender(Appender::builder().build("writer".to_owned(), Box::new(appender)))
+        .appender(Appender::builder().build("json".to_owned(), Box::new(json)))
+        .logger(Logger::builder().build("quiet".to_owned(), LogLevelFilter::Error))
+        .build(
+            Root::builder()
+                .appender("writer".to_owned())
+                .appender("json".to_owned())
+                .build(LogLevelFilter::Info),
+        ));
+    let _ = unwrap!(log4rs::init_config(config));
+
+    log04::debug!("Below the root level");
+    log04::warn!(target: "quiet", "Below the level of the target's logger");
+    log04::warn!("From the new facade");
+    warn!("From the old facade");
+
+    let timeout = Duration::from_secs(5);
+    // The file isn't forwarded by the old facade, but the module path is recovered from the target.
+    assert_eq!(
+        unwrap!(rx.recv_timeout(timeout)),
+        "WARN [new_facade_logging <unknown>] From the new facade\n"
+    );
+    assert_eq!(
+        unwrap!(rx.recv_timeout(timeout)),
+        "WARN [new_facade_logging new_facade_logging.rs] From the old facade\n"
+    );
+    assert!(rx.recv_timeout(Duration::from_millis(200)).is_err());
+
+    let locations: Vec<_> = (0..2)
+        .map(|_| {
+            let record: Value = unwrap!(serde_json::from_str(&unwrap!(
+                json_rx.recv_timeout(timeout)
+            )));
+            (record["module_path"].clone(), record["file"].clone())
+        })
+        .collect();
+    assert_eq!(
+        locations,
+        vec![
+            (Value::from("new_facade_logging"), Value::from("<unknown>")),
+            (
+                Value::from("new_facade_logging"),
+                Value::from("tests/new_facade_logging.rs")
+            ),
+        ]
+    );
+    assert!(json_rx.recv_timeout(Duration::from_millis(200)).is_err());
+}
@@ -0,0 +1,122 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+#![doc(
+    html_logo_url = "https://raw.githubusercontent.com/maidsafe/QA/master/Images/maidsafe_logo.png",
+    html_favicon_url = "https://maidsafe.net/img/favicon.ico",
+    test(attr(forbid(warnings)))
+)]
+// For explanation of lint checks, run `rustc -W help` or see
+// https://github.com/maidsafe/QA/blob/master/Documentation/Rust%20Lint%20Checks.md
+#![forbid(
+    exceeding_bitshifts,
+    mutable_transmutes,
+    no_mangle_const_items,
+    unknown_crate_types,
+    warnings
+)]
+#![deny(
+    bad_style,
+    deprecated,
+    improper_ctypes,
+    missing_docs,
+    non_shorthand_field_patterns,
+    overflowing_literals,
+    plugin_as_library,
+    stable_features,
+    unconditional_recursion,
+    unknown_lints,
+    unsafe_code,
+    unused,
+    unused_allocation,
+    unused_attributes,
+    unused_comparisons,
+    unused_features,
+    unused_parens,
+    while_true
+)]
+#![warn(
+    trivial_casts,
+    trivial_numeric_casts,
+    unused_extern_crates,
+    unused_import_braces,
+    unused_qualifications,
+    unused_results
+)]
+#![allow(
+    box_pointers,
+    missing_copy_implementations,
+    missing_debug_implementations,
+    variant_size_differences
+)]
+
+#[macro_use]
+extern crate unwrap;
+
+use log::LogLevelFilter;
+use log4rs::config::{Appender, Config, Root};
+use log4rs::encode::pattern::PatternEncoder;
+use maidsafe_utilities::log::{self as maidsafe_log, AsyncWriterAppender};
+use std::io::{self, Write};
+use std::str;
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+use std::time::Duration;
+
+struct SlowChannelWriter(Sender<String>);
+
+impl Write for SlowChannelWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // Make sure the record would still be queued if the panic hook didn't wait for it.
+        thread::sleep(Duration::from_millis(200));
+        let msg = unwrap!(str::from_utf8(buf)).to_owned();
+        self.0
+            .send(msg)
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn panic_logging() {
+    let (tx, rx) = mpsc::channel();
+
+    let appender = AsyncWriterAppender::builder(SlowChannelWriter(tx))
+        .encoder(Box::new(PatternEncoder::new("{l} {m}{n}")))
+        .build();
+    let config = unwrap!(Config::builder()
+        .appender(Appender::builder().build("writer".to_owned(), Box::new(appender)))
+        .build(
+            Root::builder()
+                .appender("writer".to_owned())
+                .build(LogLevelFilter::Error),
+        ));
+    let _ = unwrap!(log4rs::init_config(config));
+    maidsafe_log::install_panic_hook();
+
+    let result = unwrap!(thread::Builder::new()
+        .name("Worker".to_owned())
+        .spawn(|| panic!("Something went wrong")))
+    .join();
+    assert!(result.is_err());
+
+    // The record must have been written before the panicking thread finished unwinding.
+    let msg = unwrap!(rx.try_recv());
+    assert!(
+        msg.starts_with("ERROR Thread 'Worker' panicked at 'Something went wrong', "),
+        "{}",
+        msg
+    );
+    assert!(msg.contains("panic_logging.rs:"), "{}", msg);
+    assert!(msg.contains("stack backtrace:"), "{}", msg);
+}
@@ -0,0 +1,119 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+#![doc(
+    html_logo_url = "https://raw.githubusercontent.com/maidsafe/QA/master/Images/maidsafe_logo.png",
+    html_favicon_url = "https://maidsafe.net/img/favicon.ico",
+    test(attr(forbid(warnings)))
+)]
+// For explanation of lint checks, run `rustc -W help` or see
+// https://github.com/maidsafe/QA/blob/master/Documentation/Rust%20Lint%20Checks.md
+#![forbid(
+    exceeding_bitshifts,
+    mutable_transmutes,
+    no_mangle_const_items,
+    unknown_crate_types,
+    warnings
+)]
+#![deny(
+    bad_style,
+    deprecated,
+    improper_ctypes,
+    missing_docs,
+    non_shorthand_field_patterns,
+    overflowing_literals,
+    plugin_as_library,
+    stable_features,
+    unconditional_recursion,
+    unknown_lints,
+    unsafe_code,
+    unused,
+    unused_allocation,
+    unused_attributes,
+    unused_comparisons,
+    unused_features,
+    unused_parens,
+    while_true
+)]
+#![warn(
+    trivial_casts,
+    trivial_numeric_casts,
+    unused_extern_crates,
+    unused_import_braces,
+    unused_qualifications,
+    unused_results
+)]
+#![allow(
+    box_pointers,
+    missing_copy_implementations,
+    missing_debug_implementations,
+    variant_size_differences
+)]
+
+#[macro_use]
+extern crate log as logger;
+#[macro_use]
+extern crate unwrap;
+
+mod common;
+
+use crate::common::ChannelWriter;
+use log4rs::config::{Appender, Config, Root};
+use log4rs::encode::pattern::PatternEncoder;
+use logger::LogLevelFilter;
+use maidsafe_utilities::log::{AsyncWriterAppender, RateLimit};
+use std::sync::mpsc;
+use std::time::Duration;
+
+fn log_twice(message: &str) {
+    warn!("{}", message);
+    warn!("{}", message);
+}
+
+#[test]
+fn rate_limited_logging() {
+    let (tx, rx) = mpsc::channel();
+
+    let appender = AsyncWriterAppender::builder(ChannelWriter(tx))
+        .encoder(Box::new(PatternEncoder::new("{l} {m}{n}")))
+        .rate_limit(RateLimit::new().dedup_window(Duration::from_secs(3600)))
+        .build();
+    let config = unwrap!(Config::builder()
+        .appender(Appender::builder().build("writer".to_owned(), Box::new(appender)))
+        .build(
+            Root::builder()
+                .appender("writer".to_owned())
+                .build(LogLevelFilter::Trace),
+        ));
+    let _ = unwrap!(log4rs::init_config(config));
+
+    for _ in 0..100 {
+        log_twice("Repeated message");
+    }
+    log_twice("Another message");
+
+    let timeout = Duration::from_secs(5);
+    // Each of the two call sites logs the first occurrence only.
+    assert_eq!(unwrap!(rx.recv_timeout(timeout)), "WARN Repeated message\n");
+    assert_eq!(unwrap!(rx.recv_timeout(timeout)), "WARN Repeated message\n");
+    // Changing the message ends the suppression at each call site.
+    for _ in 0..2 {
+        // Summaries are written in the layout of the `init*` functions, with the location of the
+        // suppressed records.
+        let summary = unwrap!(rx.recv_timeout(timeout));
+        let mut fields = summary.splitn(3, ' ');
+        assert_eq!(fields.next(), Some("WARN"));
+        assert!(unwrap!(fields.next()).contains(':'));
+        let rest = unwrap!(fields.next());
+        assert!(rest.starts_with("[rate_limited_logging rate_limited_logging.rs:"));
+        assert!(rest.ends_with("] message repeated 99 times: Repeated message\n"));
+        assert_eq!(unwrap!(rx.recv_timeout(timeout)), "WARN Another message\n");
+    }
+    assert!(rx.recv_timeout(Duration::from_millis(200)).is_err());
+}
@@ -0,0 +1,125 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+#![doc(
+    html_logo_url = "https://raw.githubusercontent.com/maidsafe/QA/master/Images/maidsafe_logo.png",
+    html_favicon_url = "https://maidsafe.net/img/favicon.ico",
+    test(attr(forbid(warnings)))
+)]
+// For explanation of lint checks, run `rustc -W help` or see
+// https://github.com/maidsafe/QA/blob/master/Documentation/Rust%20Lint%20Checks.md
+#![forbid(
+    exceeding_bitshifts,
+    mutable_transmutes,
+    no_mangle_const_items,
+    unknown_crate_types,
+    warnings
+)]
+#![deny(
+    bad_style,
+    deprecated,
+    improper_ctypes,
+    missing_docs,
+    non_shorthand_field_patterns,
+    overflowing_literals,
+    plugin_as_library,
+    stable_features,
+    unconditional_recursion,
+    unknown_lints,
+    unsafe_code,
+    unused,
+    unused_allocation,
+    unused_attributes,
+    unused_comparisons,
+    unused_features,
+    unused_parens,
+    while_true
+)]
+#![warn(
+    trivial_casts,
+    trivial_numeric_casts,
+    unused_extern_crates,
+    unused_import_braces,
+    unused_qualifications,
+    unused_results
+)]
+#![allow(
+    box_pointers,
+    missing_copy_implementations,
+    missing_debug_implementations,
+    variant_size_differences
+)]
+
+#[macro_use]
+extern crate log as logger;
+#[macro_use]
+extern crate unwrap;
+
+use log4rs::config::{Appender, Config, Root};
+use logger::LogLevelFilter;
+use maidsafe_utilities::log::{
+    self, AsyncWriterAppender, BinaryEncoder, BinaryRecords, Encoding, PatternEncoder, Redaction,
+};
+use std::io::{self, Write};
+use std::sync::mpsc::{self, Sender};
+use std::time::Duration;
+
+struct BytesWriter(Sender<Vec<u8>>);
+
+impl Write for BytesWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0
+            .send(buf.to_vec())
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn redacted_logging() {
+    let (text_tx, text_rx) = mpsc::channel();
+    let (binary_tx, binary_rx) = mpsc::channel();
+
+    let text = AsyncWriterAppender::builder(BytesWriter(text_tx))
+        .encoder(Box::new(PatternEncoder::new("{m}{n}")))
+        .build();
+    let binary = AsyncWriterAppender::builder(BytesWriter(binary_tx))
+        .encoder(Box::new(BinaryEncoder::new()))
+        .encoding(Encoding::Binary)
+        .build();
+    let config = unwrap!(Config::builder()
+        .appender(Appender::builder().build("text".to_owned(), Box::new(text)))
+        .appender(Appender::builder().build("binary".to_owned(), Box::new(binary)))
+        .build(
+            Root::builder()
+                .appender("text".to_owned())
+                .appender("binary".to_owned())
+                .build(LogLevelFilter::Info),
+        ));
+    let _ = unwrap!(log4rs::init_config(config));
+    log::set_redaction(Redaction::new().hex_keys(8));
+
+    info!("Key deadbeef00 at 1500000000");
+
+    let timeout = Duration::from_secs(5);
+    assert_eq!(
+        unwrap!(text_rx.recv_timeout(timeout)),
+        b"Key ********** at 1500000000\n".to_vec()
+    );
+    // Masking could corrupt the numbers and lengths of binary records, so they're left intact.
+    let binary = unwrap!(binary_rx.recv_timeout(timeout));
+    let records: Vec<_> = BinaryRecords::new(&binary[..])
+        .map(|record| unwrap!(record).message)
+        .collect();
+    assert_eq!(records, vec!["Key deadbeef00 at 1500000000".to_owned()]);
+}
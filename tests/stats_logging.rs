@@ -0,0 +1,186 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+#![doc(
+    html_logo_url = "https://raw.githubusercontent.com/maidsafe/QA/master/Images/maidsafe_logo.png",
+    html_favicon_url = "https://maidsafe.net/img/favicon.ico",
+    test(attr(forbid(warnings)))
+)]
+// For explanation of lint checks, run `rustc -W help` or see
+// https://github.com/maidsafe/QA/blob/master/Documentation/Rust%20Lint%20Checks.md
+#![forbid(
+    exceeding_bitshifts,
+    mutable_transmutes,
+    no_mangle_const_items,
+    unknown_crate_types,
+    warnings
+)]
+#![deny(
+    bad_style,
+    deprecated,
+    improper_ctypes,
+    missing_docs,
+    non_shorthand_field_patterns,
+    overflowing_literals,
+    plugin_as_library,
+    stable_features,
+    unconditional_recursion,
+    unknown_lints,
+    unsafe_code,
+    unused,
+    unused_allocation,
+    unused_attributes,
+    unused_comparisons,
+    unused_features,
+    unused_parens,
+    while_true
+)]
+#![warn(
+    trivial_casts,
+    trivial_numeric_casts,
+    unused_extern_crates,
+    unused_import_braces,
+    unused_qualifications,
+    unused_results
+)]
+#![allow(
+    box_pointers,
+    missing_copy_implementations,
+    missing_debug_implementations,
+    variant_size_differences
+)]
+
+#[macro_use]
+extern crate log as logger;
+#[macro_use]
+extern crate unwrap;
+
+mod common;
+
+use crate::common::ChannelWriter;
+use log4rs::config::{Appender, Config, Root};
+use log4rs::encode::pattern::PatternEncoder;
+use log4rs::encode::{self, Encode};
+use logger::{LogLevel, LogLevelFilter, LogRecord};
+use maidsafe_utilities::log::{self, AppenderStats, AsyncWriterAppender, SampleRate, Sampling};
+use std::error::Error;
+use std::io::{self, Write};
+use std::sync::mpsc::{self, Receiver};
+use std::time::Duration;
+
+// Fails to write records containing "refuse", and blocks after sending a record containing "wait"
+// until the gate is opened.
+struct GatedWriter(ChannelWriter, Receiver<()>);
+
+impl Write for GatedWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.windows(6).any(|word| word == b"refuse") {
+            return Err(io::Error::new(io::ErrorKind::Other, "Refusing to write"));
+        }
+        let written = self.0.write(buf)?;
+        if buf.windows(4).any(|word| word == b"wait") {
+            self.1
+                .recv()
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+#[derive(Debug)]
+struct FailingEncoder(PatternEncoder);
+
+impl Encode for FailingEncoder {
+    fn encode(
+        &self,
+        writer: &mut encode::Write,
+        record: &LogRecord,
+    ) -> Result<(), Box<Error + Sync + Send>> {
+        if record.args().to_string().contains("unencodable") {
+            return Err(From::from("Refusing to encode"));
+        }
+        self.0.encode(writer, record)
+    }
+}
+
+#[test]
+fn stats_logging() {
+    let (tx, rx) = mpsc::channel();
+    let (gate_tx, gate_rx) = mpsc::channel();
+
+    let appender = AsyncWriterAppender::builder(GatedWriter(ChannelWriter(tx), gate_rx))
+        .encoder(Box::new(FailingEncoder(PatternEncoder::new("{l} {m}{n}"))))
+        .sampling(Sampling::new().rule("", LogLevel::Debug, SampleRate::OneIn(2)))
+        .build();
+    let config = unwrap!(Config::builder()
+        .appender(Appender::builder().build("writer".to_owned(), Box::new(appender)))
+        .build(
+            Root::builder()
+                .appender("writer".to_owned())
+                .build(LogLevelFilter::Trace),
+        ));
+    let _ = unwrap!(log4rs::init_config(config));
+    let timeout = Duration::from_secs(5);
+
+    // A record which fails to encode is dropped without counting against the queue depth.
+    info!("wait");
+    assert_eq!(unwrap!(rx.recv_timeout(timeout)), "INFO wait\n");
+    info!("unencodable");
+    assert_eq!(
+        log::stats(),
+        vec![AppenderStats {
+            sink: "writer".to_owned(),
+            enqueued: 1,
+            written: 0,
+            failed: 0,
+            dropped: 1,
+            queue_depth: 1,
+            bytes_written: 0,
+            reconnect_attempts: 0,
+        }]
+    );
+    unwrap!(gate_tx.send(()));
+
+    for _ in 0..3 {
+        info!("ok");
+    }
+    info!("refuse");
+    for _ in 0..4 {
+        debug!("d");
+    }
+    assert!(log::flush(Duration::from_secs(5)));
+
+    assert_eq!(
+        log::stats(),
+        vec![AppenderStats {
+            sink: "writer".to_owned(),
+            enqueued: 7,
+            written: 6,
+            failed: 1,
+            dropped: 3,
+            queue_depth: 0,
+            bytes_written: 50,
+            reconnect_attempts: 0,
+        }]
+    );
+
+    for _ in 0..5 {
+        let _ = unwrap!(rx.recv_timeout(timeout));
+    }
+    let _reporter = log::report_stats(Duration::from_millis(10), LogLevel::Warn);
+    assert_eq!(
+        unwrap!(rx.recv_timeout(timeout)),
+        "WARN writer: enqueued 7, written 6, failed 1, dropped 3, queued 0, bytes written 50, \
+         reconnect attempts 0\n"
+    );
+}
@@ -0,0 +1,112 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+#![doc(
+    html_logo_url = "https://raw.githubusercontent.com/maidsafe/QA/master/Images/maidsafe_logo.png",
+    html_favicon_url = "https://maidsafe.net/img/favicon.ico",
+    test(attr(forbid(warnings)))
+)]
+// For explanation of lint checks, run `rustc -W help` or see
+// https://github.com/maidsafe/QA/blob/master/Documentation/Rust%20Lint%20Checks.md
+#![forbid(
+    exceeding_bitshifts,
+    mutable_transmutes,
+    no_mangle_const_items,
+    unknown_crate_types,
+    warnings
+)]
+#![deny(
+    bad_style,
+    deprecated,
+    improper_ctypes,
+    missing_docs,
+    non_shorthand_field_patterns,
+    overflowing_literals,
+    plugin_as_library,
+    stable_features,
+    unconditional_recursion,
+    unknown_lints,
+    unsafe_code,
+    unused,
+    unused_allocation,
+    unused_attributes,
+    unused_comparisons,
+    unused_features,
+    unused_parens,
+    while_true
+)]
+#![warn(
+    trivial_casts,
+    trivial_numeric_casts,
+    unused_extern_crates,
+    unused_import_braces,
+    unused_qualifications,
+    unused_results
+)]
+#![allow(
+    box_pointers,
+    missing_copy_implementations,
+    missing_debug_implementations,
+    variant_size_differences
+)]
+
+#[macro_use]
+extern crate log as logger;
+#[macro_use]
+extern crate unwrap;
+
+use log::LogLevelFilter;
+use log4rs::config::{Appender, Config, Root};
+use log4rs::encode::pattern::PatternEncoder;
+use maidsafe_utilities::log::{self as maidsafe_log, AsyncWriterAppender};
+use std::io::{self, Write};
+use std::panic;
+use std::sync::mpsc;
+use std::sync::Mutex;
+use std::time::Duration;
+
+struct PanickingWriter;
+
+impl Write for PanickingWriter {
+    fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+        panic!("Could not write");
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn worker_panic_logging() {
+    let appender = AsyncWriterAppender::builder(PanickingWriter)
+        .encoder(Box::new(PatternEncoder::new("{l} {m}{n}")))
+        .build();
+    let config = unwrap!(Config::builder()
+        .appender(Appender::builder().build("writer".to_owned(), Box::new(appender)))
+        .build(
+            Root::builder()
+                .appender("writer".to_owned())
+                .build(LogLevelFilter::Error),
+        ));
+    let _ = unwrap!(log4rs::init_config(config));
+
+    // Runs after the hook installed below, once it has logged the panic.
+    let (tx, rx) = mpsc::channel();
+    let tx = Mutex::new(tx);
+    panic::set_hook(Box::new(move |_| {
+        let _ = unwrap!(tx.lock()).send(());
+    }));
+    maidsafe_log::install_panic_hook();
+
+    error!("This record makes the appender's thread panic");
+
+    // The panicking thread is the one draining the queue, so it must not wait for it.
+    assert!(rx.recv_timeout(Duration::from_secs(2)).is_ok());
+}
@@ -0,0 +1,106 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+#![doc(
+    html_logo_url = "https://raw.githubusercontent.com/maidsafe/QA/master/Images/maidsafe_logo.png",
+    html_favicon_url = "https://maidsafe.net/img/favicon.ico",
+    test(attr(forbid(warnings)))
+)]
+// For explanation of lint checks, run `rustc -W help` or see
+// https://github.com/maidsafe/QA/blob/master/Documentation/Rust%20Lint%20Checks.md
+#![forbid(
+    exceeding_bitshifts,
+    mutable_transmutes,
+    no_mangle_const_items,
+    unknown_crate_types,
+    warnings
+)]
+#![deny(
+    bad_style,
+    deprecated,
+    improper_ctypes,
+    missing_docs,
+    non_shorthand_field_patterns,
+    overflowing_literals,
+    plugin_as_library,
+    stable_features,
+    unconditional_recursion,
+    unknown_lints,
+    unsafe_code,
+    unused,
+    unused_allocation,
+    unused_attributes,
+    unused_comparisons,
+    unused_features,
+    unused_parens,
+    while_true
+)]
+#![warn(
+    trivial_casts,
+    trivial_numeric_casts,
+    unused_extern_crates,
+    unused_import_braces,
+    unused_qualifications,
+    unused_results
+)]
+#![allow(
+    box_pointers,
+    missing_copy_implementations,
+    missing_debug_implementations,
+    variant_size_differences
+)]
+
+#[macro_use]
+extern crate log as logger;
+#[macro_use]
+extern crate unwrap;
+
+mod common;
+
+use crate::common::ChannelWriter;
+use log4rs::config::{Appender, Config, Root};
+use log4rs::encode::pattern::PatternEncoder;
+use logger::LogLevelFilter;
+use maidsafe_utilities::log::AsyncWriterAppender;
+use std::sync::mpsc;
+use std::time::Duration;
+
+#[test]
+fn writer_logging() {
+    let (tx, rx) = mpsc::channel();
+
+    let appender = AsyncWriterAppender::builder(ChannelWriter(tx))
+        .encoder(Box::new(PatternEncoder::new("{l} {m}{n}")))
+        .threshold(LogLevelFilter::Warn)
+        .build();
+    let config = unwrap!(Config::builder()
+        .appender(Appender::builder().build("writer".to_owned(), Box::new(appender)))
+        .build(
+            Root::builder()
+                .appender("writer".to_owned())
+                .build(LogLevelFilter::Trace),
+        ));
+    let _ = unwrap!(log4rs::init_config(config));
+
+    info!("This message should be dropped by the appender's threshold");
+    warn!("This is message 0");
+    debug!("This message should be dropped by the appender's threshold");
+    error!("This is message 1");
+
+    let timeout = Duration::from_secs(5);
+    assert_eq!(
+        unwrap!(rx.recv_timeout(timeout)),
+        "WARN This is message 0\n"
+    );
+    assert_eq!(
+        unwrap!(rx.recv_timeout(timeout)),
+        "ERROR This is message 1\n"
+    );
+    assert!(rx.recv_timeout(Duration::from_millis(200)).is_err());
+}